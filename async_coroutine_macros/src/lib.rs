@@ -0,0 +1,212 @@
+//! Procedural macros for [`async_coroutine`](https://docs.rs/async_coroutine), re-exported by it
+//! behind the `macros` feature.
+
+#![deny(rust_2018_idioms)]
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Fields, FnArg, GenericArgument, ItemEnum, ItemFn, PathArguments, ReturnType, Type,
+    parse_macro_input, spanned::Spanned,
+};
+
+/// Turns an `async fn` whose first parameter is a `YieldHandle<Y, R>` into a function that takes
+/// the remaining parameters and returns a `Coroutine<'static, Y, T, R>` running the body.
+#[proc_macro_attribute]
+pub fn coroutine(args: TokenStream, item: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let args = proc_macro2::TokenStream::from(args);
+        return syn::Error::new(args.span(), "`#[coroutine]` does not take arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let item = parse_macro_input!(item as ItemFn);
+    match expand(item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Defines an enum for yielding several unrelated types from one coroutine.
+///
+/// Every variant has to wrap exactly one value. Besides the enum, this generates a `From` impl for
+/// every wrapped type and an extension trait for the `YieldHandle`, named after the enum with a
+/// `HandleExt` suffix, with a `yield_*` method per variant.
+#[proc_macro]
+pub fn yield_types(item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemEnum);
+    match expand_yield_types(item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = item;
+
+    if sig.asyncness.take().is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "`#[coroutine]` can only be applied to `async fn`",
+        ));
+    }
+
+    // Split off the handle parameter
+    let mut inputs = std::mem::take(&mut sig.inputs).into_iter();
+    let handle = match inputs.next() {
+        Some(FnArg::Typed(handle)) => handle,
+        Some(FnArg::Receiver(receiver)) => {
+            return Err(syn::Error::new(
+                receiver.span(),
+                "`#[coroutine]` can not be applied to methods",
+            ));
+        }
+        None => {
+            return Err(syn::Error::new(
+                sig.paren_token.span.join(),
+                "expected a `YieldHandle` as the first parameter",
+            ));
+        }
+    };
+    sig.inputs = inputs.collect();
+    let (yield_ty, resume_ty) = handle_types(&handle.ty)?;
+
+    let complete_ty = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    sig.output = syn::parse_quote! {
+        -> ::async_coroutine::Coroutine<'static, #yield_ty, #complete_ty, #resume_ty>
+    };
+
+    let handle_pat = &handle.pat;
+    let handle_ty = &handle.ty;
+    Ok(quote! {
+        #(#attrs)*
+        #[track_caller]
+        #vis #sig {
+            ::async_coroutine::Coroutine::new(
+                move |#handle_pat: #handle_ty, _| async move #block,
+            )
+        }
+    })
+}
+
+// Extracts `Y` and `R` from `YieldHandle<Y, R>`, where `R` defaults to `()`
+fn handle_types(ty: &Type) -> syn::Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    let error = || {
+        syn::Error::new(
+            ty.span(),
+            "expected a `YieldHandle<Y, R>` as the first parameter",
+        )
+    };
+
+    let Type::Path(path) = ty else {
+        return Err(error());
+    };
+    let segment = path.path.segments.last().ok_or_else(error)?;
+    if segment.ident != "YieldHandle" {
+        return Err(error());
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Err(error());
+    };
+
+    let mut types = args.args.iter().map(|arg| match arg {
+        GenericArgument::Type(ty) => Ok(quote!(#ty)),
+        _ => Err(error()),
+    });
+    let yield_ty = types.next().ok_or_else(error)??;
+    let resume_ty = types.next().transpose()?.unwrap_or_else(|| quote!(()));
+    if types.next().is_some() {
+        return Err(error());
+    }
+    Ok((yield_ty, resume_ty))
+}
+
+fn expand_yield_types(item: ItemEnum) -> syn::Result<proc_macro2::TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item.generics.span(),
+            "`yield_types!` does not support generics",
+        ));
+    }
+
+    let name = &item.ident;
+    let vis = &item.vis;
+    let ext = format_ident!("{name}HandleExt");
+
+    let mut from_impls = Vec::new();
+    let mut trait_methods = Vec::new();
+    let mut impl_methods = Vec::new();
+    for variant in &item.variants {
+        let ty = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "every variant has to wrap exactly one value, e.g. `Progress(f32)`",
+                ));
+            }
+        };
+        let variant = &variant.ident;
+        let method = format_ident!("yield_{}", snake_case(&variant.to_string()));
+        let doc = format!("Yields a [`{name}::{variant}`] and receives back the resume value.");
+
+        from_impls.push(quote! {
+            impl ::std::convert::From<#ty> for #name {
+                fn from(value: #ty) -> Self {
+                    Self::#variant(value)
+                }
+            }
+        });
+        trait_methods.push(quote! {
+            #[doc = #doc]
+            fn #method(&self, value: #ty) -> impl ::std::future::Future<Output = R>;
+        });
+        impl_methods.push(quote! {
+            fn #method(&self, value: #ty) -> impl ::std::future::Future<Output = R> {
+                self.yield_(#name::#variant(value))
+            }
+        });
+    }
+
+    let doc = format!("Typed `yield_*` methods for the variants of [`{name}`].");
+    Ok(quote! {
+        #item
+
+        #(#from_impls)*
+
+        #[doc = #doc]
+        #vis trait #ext<R> {
+            #(#trait_methods)*
+        }
+
+        impl<R> #ext<R> for ::async_coroutine::YieldHandle<#name, R> {
+            #(#impl_methods)*
+        }
+    })
+}
+
+// Converts `UpperCamelCase` to `snake_case`
+fn snake_case(ident: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in ident.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
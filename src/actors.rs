@@ -0,0 +1,245 @@
+//! Single-threaded actors, i.e. coroutines that are fed from a mailbox.
+//!
+//! An [`Actor`] wraps a coroutine together with a mailbox. Other code enqueues messages through
+//! cloneable [`Address`]es, and [`Actor::process`] resumes the coroutine with every queued message
+//! in order and dispatches its yields to the subscribers.
+//!
+//! ```
+//! use async_coroutine::{Coroutine, actors::Actor};
+//! use std::{cell::RefCell, rc::Rc};
+//!
+//! let mut counter = Actor::new(Coroutine::new(|handle, mut amount: u32| async move {
+//!     let mut total = 0;
+//!     while amount > 0 {
+//!         total += amount;
+//!         amount = handle.yield_(total).await;
+//!     }
+//!     total
+//! }));
+//!
+//! let totals = Rc::new(RefCell::new(Vec::new()));
+//! counter.subscribe({
+//!     let totals = Rc::clone(&totals);
+//!     move |total| totals.borrow_mut().push(*total)
+//! });
+//!
+//! let address = counter.address();
+//! address.send(1).unwrap();
+//! address.send(2).unwrap();
+//! assert_eq!(counter.process(), 2);
+//! assert_eq!(*totals.borrow(), [1, 3]);
+//!
+//! address.send(0).unwrap();
+//! counter.process();
+//! assert_eq!(counter.take_complete(), Some(3));
+//! assert!(address.send(4).is_err());
+//! ```
+
+use crate::{Completed, Coroutine, State};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+/// A coroutine that is resumed with the messages sent to its mailbox.
+pub struct Actor<Y, T, R> {
+    coroutine: Coroutine<'static, Y, T, R>,
+    mailbox: Rc<Mailbox<R>>,
+    #[expect(clippy::type_complexity)]
+    subscribers: Vec<Box<dyn FnMut(&Y)>>,
+    complete: Option<T>,
+}
+
+struct Mailbox<R> {
+    messages: RefCell<VecDeque<R>>,
+    // Set once the coroutine completed, rejects further messages
+    closed: Cell<bool>,
+}
+
+impl<Y, T, R> Actor<Y, T, R> {
+    /// Wraps a coroutine with an empty mailbox. The first message becomes its initial value.
+    pub fn new(coroutine: Coroutine<'static, Y, T, R>) -> Self {
+        Self {
+            coroutine,
+            mailbox: Rc::new(Mailbox {
+                messages: RefCell::new(VecDeque::new()),
+                closed: Cell::new(false),
+            }),
+            subscribers: Vec::new(),
+            complete: None,
+        }
+    }
+
+    /// Returns an address that enqueues messages in the mailbox of the actor.
+    pub fn address(&self) -> Address<R> {
+        Address {
+            mailbox: Rc::downgrade(&self.mailbox),
+        }
+    }
+
+    /// Registers a subscriber that is called with every value the coroutine yields.
+    ///
+    /// Subscribers are called in the order they were registered.
+    pub fn subscribe(&mut self, subscriber: impl FnMut(&Y) + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    /// Returns the number of messages waiting in the mailbox.
+    pub fn pending(&self) -> usize {
+        self.mailbox.messages.borrow().len()
+    }
+
+    /// Resumes the coroutine with the next message, if there is one, and dispatches its yield to
+    /// the subscribers.
+    ///
+    /// Returns `false` if the mailbox was empty or the coroutine has completed.
+    #[track_caller]
+    pub fn process_one(&mut self) -> bool {
+        if self.mailbox.closed.get() {
+            return false;
+        }
+        let Some(message) = self.mailbox.messages.borrow_mut().pop_front() else {
+            return false;
+        };
+
+        match self.coroutine.resume_with(message) {
+            State::Yield(value) => {
+                for subscriber in &mut self.subscribers {
+                    subscriber(&value);
+                }
+            }
+            State::Complete(value) => {
+                self.complete = Some(value);
+                self.mailbox.closed.set(true);
+                self.mailbox.messages.borrow_mut().clear();
+            }
+        }
+        true
+    }
+
+    /// Processes messages until the mailbox is empty or the coroutine completes, including the
+    /// messages sent by subscribers in the meantime.
+    ///
+    /// Returns the number of processed messages.
+    #[track_caller]
+    pub fn process(&mut self) -> usize {
+        let mut processed = 0;
+        while self.process_one() {
+            processed += 1;
+        }
+        processed
+    }
+
+    /// Returns `true` if the coroutine has completed.
+    pub fn is_completed(&self) -> bool {
+        self.mailbox.closed.get()
+    }
+
+    /// Takes the completion value of the coroutine, if it has completed and the value has not been
+    /// taken yet.
+    pub fn take_complete(&mut self) -> Option<T> {
+        self.complete.take()
+    }
+}
+
+impl<Y, T, R> fmt::Debug for Actor<Y, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Actor")
+            .field("coroutine", &self.coroutine)
+            .field("pending", &self.pending())
+            .field("subscribers", &self.subscribers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle for sending messages to an [`Actor`], created by [`Actor::address`].
+pub struct Address<R> {
+    mailbox: Weak<Mailbox<R>>,
+}
+
+impl<R> Address<R> {
+    /// Enqueues a message in the mailbox of the actor.
+    ///
+    /// Returns [`Completed`] and drops the message if the coroutine of the actor has completed or
+    /// the actor was dropped.
+    pub fn send(&self, message: R) -> Result<(), Completed> {
+        match self.mailbox.upgrade() {
+            Some(mailbox) if !mailbox.closed.get() => {
+                mailbox.messages.borrow_mut().push_back(message);
+                Ok(())
+            }
+            _ => Err(Completed),
+        }
+    }
+
+    /// Returns `true` if the actor still accepts messages.
+    pub fn is_connected(&self) -> bool {
+        self.mailbox
+            .upgrade()
+            .is_some_and(|mailbox| !mailbox.closed.get())
+    }
+}
+
+impl<R> Clone for Address<R> {
+    fn clone(&self) -> Self {
+        Self {
+            mailbox: Weak::clone(&self.mailbox),
+        }
+    }
+}
+
+impl<R> fmt::Debug for Address<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Address")
+            .field("connected", &self.is_connected())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Actor;
+    use crate::{Completed, Coroutine};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_actor_messages_from_subscriber() {
+        let mut echo = Actor::new(Coroutine::new(|handle, mut message: u32| async move {
+            while message > 0 {
+                message = handle.yield_(message - 1).await;
+            }
+        }));
+
+        // Feeds every yield back into the mailbox until the coroutine completes
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let address = echo.address();
+        echo.subscribe({
+            let received = Rc::clone(&received);
+            move |value| {
+                received.borrow_mut().push(*value);
+                address.send(*value).unwrap();
+            }
+        });
+
+        let address = echo.address();
+        address.send(3).unwrap();
+        assert_eq!(echo.pending(), 1);
+        assert_eq!(echo.process(), 4);
+        assert_eq!(*received.borrow(), [2, 1, 0]);
+        assert!(echo.is_completed());
+        assert_eq!(echo.take_complete(), Some(()));
+        assert!(!address.is_connected());
+        assert_eq!(address.send(1), Err(Completed));
+    }
+
+    #[test]
+    fn test_actor_dropped() {
+        let actor = Actor::<(), (), u8>::new(Coroutine::new(|_handle, _| async {}));
+        let address = actor.address();
+        assert!(address.is_connected());
+        drop(actor);
+        assert_eq!(address.send(1), Err(Completed));
+    }
+}
@@ -0,0 +1,355 @@
+//! Adapters that wrap a [`Coroutine`] and change how its yields are observed.
+
+use crate::{Coroutine, Generator, Resumable, State};
+use std::{cell::RefCell, rc::Rc};
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
+    /// Creates an adapter that suppresses consecutive equal yields.
+    ///
+    /// Whenever the coroutine yields a value equal to the previously yielded one, it is resumed
+    /// again with a clone of the same resume value until a distinct value is yielded or it
+    /// completes.
+    pub fn dedup(self) -> Dedup<'a, Y, T, R>
+    where
+        Y: PartialEq + Clone,
+        R: Clone,
+    {
+        Dedup {
+            inner: self,
+            last: None,
+        }
+    }
+
+    /// Creates an adapter that suppresses consecutive yields which map to the same key.
+    ///
+    /// See [`Coroutine::dedup`] for how suppressed yields are resumed.
+    pub fn dedup_by_key<F, K>(self, key: F) -> DedupByKey<'a, Y, T, R, F, K>
+    where
+        F: FnMut(&Y) -> K,
+        K: PartialEq,
+        R: Clone,
+    {
+        DedupByKey {
+            inner: self,
+            key,
+            last: None,
+        }
+    }
+}
+
+impl<'a, Y, T> Generator<'a, Y, T> {
+    /// Creates a cursor that records the yielded values so the sequence can be iterated multiple
+    /// times.
+    ///
+    /// Cloning the returned [`Cached`] creates another cursor at the same position that shares the
+    /// recorded values. The generator is only resumed when a cursor reaches the end of the
+    /// recorded values.
+    pub fn cached(self) -> Cached<'a, Y, T> {
+        Cached {
+            cache: Rc::new(RefCell::new(Cache {
+                generator: self,
+                values: Vec::new(),
+                complete: None,
+            })),
+            position: 0,
+        }
+    }
+
+    /// Splits the generator into two independent cursors over the same sequence of values.
+    ///
+    /// This is a shorthand for [`Generator::cached`] followed by a clone.
+    pub fn tee(self) -> (Cached<'a, Y, T>, Cached<'a, Y, T>) {
+        let cached = self.cached();
+        (cached.clone(), cached)
+    }
+}
+
+/// A coroutine adapter that suppresses consecutive equal yields, created by [`Coroutine::dedup`].
+pub struct Dedup<'a, Y, T, R> {
+    inner: Coroutine<'a, Y, T, R>,
+    last: Option<Y>,
+}
+
+impl<'a, Y, T, R> Dedup<'a, Y, T, R>
+where
+    Y: PartialEq + Clone,
+    R: Clone,
+{
+    /// Resumes the underlying coroutine until it yields a value that differs from the previous
+    /// one or completes.
+    pub fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        loop {
+            match self.inner.resume_with(resume.clone()) {
+                State::Yield(value) => {
+                    if self.last.as_ref() != Some(&value) {
+                        self.last = Some(value.clone());
+                        break State::Yield(value);
+                    }
+                }
+                State::Complete(value) => break State::Complete(value),
+            }
+        }
+    }
+
+    /// Consumes the adapter, returning the underlying coroutine.
+    pub fn into_inner(self) -> Coroutine<'a, Y, T, R> {
+        self.inner
+    }
+}
+
+impl<Y, T, R> Resumable<R> for Dedup<'_, Y, T, R>
+where
+    Y: PartialEq + Clone,
+    R: Clone,
+{
+    type Yield = Y;
+    type Complete = T;
+
+    fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        Dedup::resume_with(self, resume)
+    }
+}
+
+impl<Y, T> Dedup<'_, Y, T, ()>
+where
+    Y: PartialEq + Clone,
+{
+    /// Resumes the underlying generator.
+    pub fn resume(&mut self) -> State<Y, T> {
+        self.resume_with(())
+    }
+}
+
+/// A coroutine adapter that suppresses consecutive yields with equal keys, created by
+/// [`Coroutine::dedup_by_key`].
+pub struct DedupByKey<'a, Y, T, R, F, K> {
+    inner: Coroutine<'a, Y, T, R>,
+    key: F,
+    last: Option<K>,
+}
+
+impl<'a, Y, T, R, F, K> DedupByKey<'a, Y, T, R, F, K>
+where
+    R: Clone,
+    F: FnMut(&Y) -> K,
+    K: PartialEq,
+{
+    /// Resumes the underlying coroutine until it yields a value whose key differs from the
+    /// previous one or completes.
+    pub fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        loop {
+            match self.inner.resume_with(resume.clone()) {
+                State::Yield(value) => {
+                    let key = (self.key)(&value);
+                    if self.last.as_ref() != Some(&key) {
+                        self.last = Some(key);
+                        break State::Yield(value);
+                    }
+                }
+                State::Complete(value) => break State::Complete(value),
+            }
+        }
+    }
+
+    /// Consumes the adapter, returning the underlying coroutine.
+    pub fn into_inner(self) -> Coroutine<'a, Y, T, R> {
+        self.inner
+    }
+}
+
+impl<Y, T, R, F, K> Resumable<R> for DedupByKey<'_, Y, T, R, F, K>
+where
+    R: Clone,
+    F: FnMut(&Y) -> K,
+    K: PartialEq,
+{
+    type Yield = Y;
+    type Complete = T;
+
+    fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        DedupByKey::resume_with(self, resume)
+    }
+}
+
+impl<Y, T, F, K> DedupByKey<'_, Y, T, (), F, K>
+where
+    F: FnMut(&Y) -> K,
+    K: PartialEq,
+{
+    /// Resumes the underlying generator.
+    pub fn resume(&mut self) -> State<Y, T> {
+        self.resume_with(())
+    }
+}
+
+/// A cursor over the recorded values of a generator, created by [`Generator::cached`].
+pub struct Cached<'a, Y, T> {
+    cache: Rc<RefCell<Cache<'a, Y, T>>>,
+    position: usize,
+}
+
+struct Cache<'a, Y, T> {
+    generator: Generator<'a, Y, T>,
+    values: Vec<Y>,
+    complete: Option<T>,
+}
+
+impl<Y, T> Cached<'_, Y, T>
+where
+    Y: Clone,
+    T: Clone,
+{
+    /// Returns the next value of the sequence, resuming the generator if this cursor has reached
+    /// the end of the recorded values.
+    ///
+    /// Once the generator has completed, every cursor at the end of the sequence keeps returning a
+    /// clone of the completion value.
+    pub fn resume(&mut self) -> State<Y, T> {
+        match self.advance() {
+            Some(value) => State::Yield(value),
+            None => State::Complete(self.cache.borrow().complete.clone().unwrap()),
+        }
+    }
+}
+
+impl<Y, T> Cached<'_, Y, T>
+where
+    Y: Clone,
+{
+    /// Moves the cursor back to the start of the sequence.
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+
+    /// Returns the number of values recorded so far.
+    pub fn recorded(&self) -> usize {
+        self.cache.borrow().values.len()
+    }
+
+    // Returns `None` if the generator has completed
+    fn advance(&mut self) -> Option<Y> {
+        let mut cache = self.cache.borrow_mut();
+        if self.position == cache.values.len() {
+            if cache.complete.is_some() {
+                return None;
+            }
+            match cache.generator.resume() {
+                State::Yield(value) => cache.values.push(value),
+                State::Complete(value) => {
+                    cache.complete = Some(value);
+                    return None;
+                }
+            }
+        }
+
+        self.position += 1;
+        Some(cache.values[self.position - 1].clone())
+    }
+}
+
+impl<Y, T> Resumable<()> for Cached<'_, Y, T>
+where
+    Y: Clone,
+    T: Clone,
+{
+    type Yield = Y;
+    type Complete = T;
+
+    fn resume_with(&mut self, (): ()) -> State<Y, T> {
+        self.resume()
+    }
+}
+
+impl<Y, T> Clone for Cached<'_, Y, T> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: Rc::clone(&self.cache),
+            position: self.position,
+        }
+    }
+}
+
+impl<Y, T> Iterator for Cached<'_, Y, T>
+where
+    Y: Clone,
+{
+    type Item = Y;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Coroutine, Generator, State};
+
+    #[test]
+    fn test_dedup() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            for value in [1, 1, 2, 2, 2, 1, 3, 3] {
+                handle.yield_(value).await;
+            }
+            "Bye"
+        })
+        .dedup();
+
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(2));
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(3));
+        assert_eq!(generator.resume(), State::Complete("Bye"));
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let mut co = Coroutine::new(|handle, mut sum| async move {
+            for value in [1, 3, 2, 4, 5] {
+                sum += handle.yield_(value).await;
+            }
+            sum
+        })
+        .dedup_by_key(|value| value % 2);
+
+        assert_eq!(co.resume_with(0), State::Yield(1));
+        assert_eq!(co.resume_with(10), State::Yield(2));
+        assert_eq!(co.resume_with(100), State::Yield(5));
+        assert_eq!(co.resume_with(1000), State::Complete(1220));
+    }
+
+    #[test]
+    fn test_cached() {
+        let mut first = Generator::new(|handle, ()| async move {
+            for value in 1..=3 {
+                handle.yield_(value).await;
+            }
+            "Bye"
+        })
+        .cached();
+        let mut second = first.clone();
+
+        assert_eq!(first.resume(), State::Yield(1));
+        assert_eq!(first.resume(), State::Yield(2));
+        assert_eq!(second.resume(), State::Yield(1));
+        assert_eq!(first.recorded(), 2);
+        assert_eq!(first.resume(), State::Yield(3));
+        assert_eq!(first.resume(), State::Complete("Bye"));
+        assert_eq!(second.by_ref().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(second.resume(), State::Complete("Bye"));
+
+        second.rewind();
+        assert_eq!(second.collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_tee() {
+        let (left, right) = Generator::new(|handle, ()| async move {
+            handle.yield_('a').await;
+            handle.yield_('b').await;
+        })
+        .tee();
+
+        assert_eq!(left.collect::<String>(), "ab");
+        assert_eq!(right.collect::<String>(), "ab");
+    }
+}
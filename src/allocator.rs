@@ -0,0 +1,174 @@
+// Allocating the body of a coroutine in a caller-provided allocator
+
+use crate::{Coroutine, CoroutineBuilder, ExecutorState, YieldHandle, executor::Body};
+use allocator_api2::{alloc::Allocator, boxed::Box};
+use std::{
+    future::Future,
+    mem::MaybeUninit,
+    pin::Pin,
+    ptr::{self, NonNull},
+    task::{Context, Poll},
+};
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
+    /// Creates a new coroutine like [`Coroutine::new`], but allocates the future returned by `f`
+    /// in the given allocator.
+    ///
+    /// The future holds the state of the body across yields and is by far the largest allocation
+    /// of a coroutine. The bookkeeping of the coroutine, i.e. its internal channel and queues and
+    /// `f` until the first resume, is still allocated globally.
+    #[track_caller]
+    pub fn new_in<A, F>(alloc: A, f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'a) -> Self
+    where
+        A: Allocator + 'a,
+        F: Future<Output = T> + 'a,
+    {
+        Self::builder().build_in(alloc, f)
+    }
+}
+
+impl<'a, Y, T, R> CoroutineBuilder<'a, Y, T, R> {
+    /// Creates the coroutine like [`CoroutineBuilder::build`], but allocates the future returned
+    /// by `f` in the given allocator.
+    ///
+    /// See [`Coroutine::new_in`] for details.
+    #[track_caller]
+    pub fn build_in<A, F>(
+        self,
+        alloc: A,
+        f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'a,
+    ) -> Coroutine<'a, Y, T, R>
+    where
+        A: Allocator + 'a,
+        F: Future<Output = T> + 'a,
+    {
+        self.build_executor(ExecutorState::from_init(move |handle, initial_value| {
+            Body::InAlloc(AllocBody::new_in(f(handle, initial_value), alloc))
+        }))
+    }
+}
+
+// A future allocated in `A`, which is stored next to it so the body is a single allocation
+pub(crate) struct AllocBody<'a, T> {
+    future: NonNull<dyn Future<Output = T> + 'a>,
+    allocation: NonNull<()>,
+    release: unsafe fn(NonNull<()>),
+}
+
+// Only written once the allocation exists, so it is not dropped with the future
+struct InAlloc<F, A> {
+    future: F,
+    alloc: MaybeUninit<A>,
+}
+
+impl<'a, T> AllocBody<'a, T> {
+    fn new_in<F, A>(future: F, alloc: A) -> Self
+    where
+        F: Future<Output = T> + 'a,
+        A: Allocator + 'a,
+    {
+        let boxed = Box::new_in(
+            InAlloc {
+                future,
+                alloc: MaybeUninit::uninit(),
+            },
+            alloc,
+        );
+        let (allocation, alloc) = Box::into_raw_with_allocator(boxed);
+        // SAFETY: The pointer comes from a box, so it is valid and not null
+        unsafe {
+            (*allocation).alloc.write(alloc);
+            let future: NonNull<F> = NonNull::new_unchecked(&raw mut (*allocation).future);
+            Self {
+                future,
+                allocation: NonNull::new_unchecked(allocation).cast(),
+                release: release::<F, A>,
+            }
+        }
+    }
+
+    pub(crate) fn poll(&mut self, context: &mut Context<'_>) -> Poll<T> {
+        // SAFETY: The future is never moved out of its allocation
+        unsafe { Pin::new_unchecked(self.future.as_mut()) }.poll(context)
+    }
+}
+
+impl<T> Drop for AllocBody<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: `release` matches the types the allocation was created with
+        unsafe { (self.release)(self.allocation) }
+    }
+}
+
+// Drops the future and gives its allocation back to the allocator stored next to it
+unsafe fn release<F, A>(allocation: NonNull<()>)
+where
+    A: Allocator,
+{
+    let allocation = allocation.cast::<InAlloc<F, A>>().as_ptr();
+    // SAFETY: The allocation was created by `AllocBody::new_in`, which wrote the allocator
+    unsafe {
+        let alloc = ptr::read(&raw const (*allocation).alloc).assume_init();
+        drop(Box::from_raw_in(allocation, alloc));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Coroutine, State};
+    use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
+    use std::{cell::Cell, ptr::NonNull, rc::Rc};
+
+    #[derive(Clone, Default)]
+    struct Counting {
+        allocated: Rc<Cell<usize>>,
+    }
+
+    unsafe impl Allocator for Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.allocated.set(self.allocated.get() + layout.size());
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.allocated.set(self.allocated.get() - layout.size());
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn test_new_in() {
+        let alloc = Counting::default();
+        let mut co = Coroutine::new_in(alloc.clone(), |handle, value| async move {
+            let buffer = [value; 64];
+            let next = handle.yield_(buffer[0]).await;
+            buffer.iter().sum::<u8>() + next
+        });
+
+        assert_eq!(alloc.allocated.get(), 0);
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert!(alloc.allocated.get() >= 64);
+        assert_eq!(co.resume_with(2), State::Complete(66));
+        drop(co);
+        assert_eq!(alloc.allocated.get(), 0);
+    }
+
+    #[test]
+    fn test_new_in_borrowed() {
+        let alloc = Counting::default();
+        let alive = Rc::new(());
+        let mut co = Coroutine::new_in(&alloc, {
+            let alive = Rc::clone(&alive);
+            |handle, ()| async move {
+                let _alive = alive;
+                handle.yield_(1).await;
+            }
+        });
+
+        assert_eq!(co.resume(), State::Yield(1));
+        assert!(alloc.allocated.get() > 0);
+        drop(co);
+        assert_eq!(Rc::strong_count(&alive), 1);
+        assert_eq!(alloc.allocated.get(), 0);
+    }
+}
@@ -0,0 +1,114 @@
+//! Exposing coroutines as leaves of a behavior tree.
+
+use crate::{Coroutine, State};
+
+/// The status of a behavior tree node after a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// The node needs more ticks to finish.
+    Running,
+    /// The node finished successfully.
+    Success,
+    /// The node finished unsuccessfully.
+    Failure,
+}
+
+/// A behavior tree leaf that runs a coroutine, which is resumed once per tick and completes with
+/// whether it succeeded.
+///
+/// The coroutine is restartable, the leaf resets it once it finished or was reset, so the next
+/// tick runs the body again from the beginning.
+pub struct Leaf<'a, R> {
+    coroutine: Coroutine<'a, (), bool, R>,
+    running: bool,
+}
+
+impl<'a, R> Leaf<'a, R> {
+    /// Creates a leaf that runs `coroutine`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine is not restartable, see [`Coroutine::restartable`].
+    #[track_caller]
+    pub fn new(coroutine: Coroutine<'a, (), bool, R>) -> Self {
+        assert!(
+            coroutine.is_restartable(),
+            "{} is not restartable",
+            coroutine.yield_handle.shared.origin,
+        );
+        Self {
+            coroutine,
+            running: false,
+        }
+    }
+
+    /// Resumes the coroutine, starting it from the beginning if the leaf is not running.
+    ///
+    /// Maps yields to [`Status::Running`] and the completion to [`Status::Success`] or
+    /// [`Status::Failure`].
+    pub fn tick(&mut self, resume: R) -> Status {
+        self.running = true;
+        match self.coroutine.resume_with(resume) {
+            State::Yield(()) => Status::Running,
+            State::Complete(success) => {
+                self.reset();
+                if success {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the coroutine has been started and has not finished yet.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Aborts the running coroutine, so the next tick starts it from the beginning.
+    ///
+    /// This is what a parent node does when it interrupts the leaf.
+    pub fn reset(&mut self) {
+        if self.running {
+            self.coroutine.reset();
+            self.running = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Leaf, Status};
+    use crate::Coroutine;
+
+    #[test]
+    fn test_leaf() {
+        let mut leaf = Leaf::new(Coroutine::restartable(
+            |handle, mut target: i32| async move {
+                for _ in 0..2 {
+                    target = handle.yield_(()).await;
+                }
+                target > 0
+            },
+        ));
+
+        assert_eq!(leaf.tick(0), Status::Running);
+        assert_eq!(leaf.tick(0), Status::Running);
+        assert_eq!(leaf.tick(1), Status::Success);
+        assert!(!leaf.is_running());
+
+        assert_eq!(leaf.tick(0), Status::Running);
+        leaf.reset();
+        assert!(!leaf.is_running());
+        assert_eq!(leaf.tick(0), Status::Running);
+        assert_eq!(leaf.tick(0), Status::Running);
+        assert_eq!(leaf.tick(-1), Status::Failure);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not restartable")]
+    fn test_leaf_not_restartable() {
+        Leaf::new(Coroutine::new(|_, ()| async { true }));
+    }
+}
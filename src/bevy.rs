@@ -0,0 +1,131 @@
+//! Integration with Bevy for scripts that advance once per frame.
+//!
+//! Add the [`CoroutinePlugin`] to the app and spawn [`ScriptCoroutine`]s through the
+//! [`Coroutines`] resource. Every coroutine is resumed once per [`Update`] with the current
+//! [`Time`] and belongs to an entity. When it completes, a [`CoroutineCompleted`] message is written
+//! and the entity is despawned if it has the [`DespawnOnComplete`] component. Coroutines whose
+//! entity no longer exists are dropped.
+//!
+//! Coroutines are not `Send`, so [`Coroutines`] is a non-send resource and has to be accessed with
+//! [`NonSendMut`].
+
+use crate::{Coroutine, State};
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::prelude::*;
+use bevy_time::Time;
+
+/// A coroutine that is resumed once per frame with the current [`Time`].
+pub type ScriptCoroutine = Coroutine<'static, (), (), Time>;
+
+/// A plugin that resumes the coroutines of the [`Coroutines`] resource every [`Update`].
+#[derive(Debug, Default)]
+pub struct CoroutinePlugin;
+
+impl Plugin for CoroutinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_non_send::<Coroutines>()
+            .add_message::<CoroutineCompleted>()
+            .add_systems(Update, resume_coroutines);
+    }
+}
+
+/// The non-send resource holding the running coroutines.
+#[derive(Default)]
+pub struct Coroutines {
+    running: Vec<(Entity, ScriptCoroutine)>,
+}
+
+impl Coroutines {
+    /// Starts running a coroutine that belongs to the given entity.
+    ///
+    /// It is resumed for the first time in the next [`Update`].
+    pub fn spawn(&mut self, entity: Entity, coroutine: ScriptCoroutine) {
+        self.running.push((entity, coroutine));
+    }
+
+    /// Returns the number of running coroutines.
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Returns `true` if no coroutines are running.
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+}
+
+/// The message written when a coroutine completes.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoroutineCompleted {
+    /// The entity the coroutine belonged to.
+    pub entity: Entity,
+}
+
+/// Marks entities that are despawned when their coroutine completes.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct DespawnOnComplete;
+
+fn resume_coroutines(
+    mut coroutines: NonSendMut<'_, Coroutines>,
+    time: Res<'_, Time>,
+    entities: Query<'_, '_, Has<DespawnOnComplete>>,
+    mut completed: MessageWriter<'_, CoroutineCompleted>,
+    mut commands: Commands<'_, '_>,
+) {
+    coroutines.running.retain_mut(|(entity, coroutine)| {
+        let Ok(despawn) = entities.get(*entity) else {
+            return false;
+        };
+        match coroutine.resume_with(*time) {
+            State::Yield(()) => true,
+            State::Complete(()) => {
+                completed.write(CoroutineCompleted { entity: *entity });
+                if despawn {
+                    commands.entity(*entity).despawn();
+                }
+                false
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CoroutineCompleted, CoroutinePlugin, Coroutines, DespawnOnComplete};
+    use crate::Coroutine;
+    use bevy_app::App;
+    use bevy_ecs::prelude::*;
+    use bevy_time::Time;
+    use std::time::Duration;
+
+    #[test]
+    fn test_plugin() {
+        let mut app = App::new();
+        app.add_plugins(CoroutinePlugin).init_resource::<Time>();
+
+        let entity = app.world_mut().spawn(DespawnOnComplete).id();
+        app.world_mut().non_send_mut::<Coroutines>().spawn(
+            entity,
+            Coroutine::new(|handle, mut time: Time| async move {
+                while time.elapsed() < Duration::from_secs(1) {
+                    time = handle.yield_(()).await;
+                }
+            }),
+        );
+
+        app.update();
+        assert!(app.world().get_entity(entity).is_ok());
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+
+        assert!(app.world().get_entity(entity).is_err());
+        assert!(app.world().non_send::<Coroutines>().is_empty());
+        let messages = app.world().resource::<Messages<CoroutineCompleted>>();
+        assert_eq!(
+            messages.iter_current_update_messages().collect::<Vec<_>>(),
+            [&CoroutineCompleted { entity }]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Helpers for driving a coroutine with messages from other tokio tasks.
+
+use crate::{Coroutine, State};
+use futures_core::{Stream, stream::FusedStream};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::{mpsc, oneshot};
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
+    /// Drives the coroutine with the messages received from `receiver`.
+    ///
+    /// Every received message resumes the coroutine once and the yielded values are produced by
+    /// the returned [`Bridge`] stream. When the coroutine completes, the stream ends and the
+    /// completion value is sent to the returned oneshot receiver. If the channel is closed before
+    /// the coroutine completes, the stream ends and the oneshot receiver reports an error.
+    pub fn bridge(
+        self,
+        receiver: mpsc::Receiver<R>,
+    ) -> (Bridge<'a, Y, T, R>, oneshot::Receiver<T>) {
+        let (sender, complete) = oneshot::channel();
+        let bridge = Bridge {
+            coroutine: Some(self),
+            receiver,
+            complete: Some(sender),
+        };
+        (bridge, complete)
+    }
+}
+
+/// A stream of the values yielded by a coroutine that is driven by channel messages, created by
+/// [`Coroutine::bridge`].
+pub struct Bridge<'a, Y, T, R> {
+    coroutine: Option<Coroutine<'a, Y, T, R>>,
+    receiver: mpsc::Receiver<R>,
+    complete: Option<oneshot::Sender<T>>,
+}
+
+impl<Y, T, R> Stream for Bridge<'_, Y, T, R> {
+    type Item = Y;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(coroutine) = &mut this.coroutine else {
+            return Poll::Ready(None);
+        };
+
+        match this.receiver.poll_recv(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(None) => {
+                // Dropping the sender notifies the completion receiver
+                this.coroutine = None;
+                this.complete = None;
+                Poll::Ready(None)
+            }
+            Poll::Ready(Some(resume)) => match coroutine.resume_with(resume) {
+                State::Yield(value) => Poll::Ready(Some(value)),
+                State::Complete(value) => {
+                    this.coroutine = None;
+                    if let Some(complete) = this.complete.take() {
+                        // The receiver might not be interested in the completion value
+                        let _ = complete.send(value);
+                    }
+                    Poll::Ready(None)
+                }
+            },
+        }
+    }
+}
+
+impl<Y, T, R> FusedStream for Bridge<'_, Y, T, R> {
+    fn is_terminated(&self) -> bool {
+        self.coroutine.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Coroutine;
+    use futures_core::{Stream, stream::FusedStream};
+    use std::{
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_bridge() {
+        let co = Coroutine::new(|handle, mut sum| async move {
+            while sum < 10 {
+                sum += handle.yield_(sum).await;
+            }
+            sum
+        });
+
+        let (sender, receiver) = mpsc::channel(4);
+        let (mut stream, mut complete) = co.bridge(receiver);
+        let mut context = Context::from_waker(Waker::noop());
+        let mut next = || Pin::new(&mut stream).poll_next(&mut context);
+
+        assert_eq!(next(), Poll::Pending);
+        sender.try_send(3).unwrap();
+        sender.try_send(4).unwrap();
+        assert_eq!(next(), Poll::Ready(Some(3)));
+        assert_eq!(next(), Poll::Ready(Some(7)));
+        assert_eq!(next(), Poll::Pending);
+        assert!(complete.try_recv().is_err());
+        sender.try_send(5).unwrap();
+        assert_eq!(next(), Poll::Ready(None));
+        assert!(stream.is_terminated());
+        assert_eq!(complete.try_recv(), Ok(12));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Coroutine, ExecutorState, Observer, SpinPolicy, YieldHandle, YieldPolicy};
+use std::{any::Any, future::Future, rc::Rc};
+
+/// A builder for configuring a [`Coroutine`] before creating it, created by
+/// [`Coroutine::builder`].
+pub struct CoroutineBuilder<'a, Y, T, R> {
+    config: Config<'a, Y, T, R>,
+}
+
+impl<'a, Y, T, R> CoroutineBuilder<'a, Y, T, R> {
+    /// Creates a builder with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            config: Config {
+                name: None,
+                spin_policy: SpinPolicy::default(),
+                yield_policy: YieldPolicy::default(),
+                yield_interval: None,
+                size_hint: None,
+                on_yield: None,
+                observers: Vec::new(),
+                implicit_handle: None,
+                context: None,
+                register: false,
+            },
+        }
+    }
+
+    /// Sets the name of the coroutine, which is used to identify it in panic messages.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = Some(name.into());
+        self
+    }
+
+    /// Sets the [`SpinPolicy`] of the coroutine.
+    pub fn spin_policy(mut self, spin_policy: SpinPolicy) -> Self {
+        self.config.spin_policy = spin_policy;
+        self
+    }
+
+    /// Sets the [`YieldPolicy`] of the coroutine.
+    pub fn yield_policy(mut self, yield_policy: YieldPolicy) -> Self {
+        self.config.yield_policy = yield_policy;
+        self
+    }
+
+    /// Makes [`YieldHandle::maybe_yield`] yield a heartbeat on every `interval`-th call since the
+    /// body last suspended.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn yield_interval(mut self, interval: usize) -> Self {
+        assert!(interval > 0, "the yield interval must not be zero");
+        self.config.yield_interval = Some(interval);
+        self
+    }
+
+    /// Sets the number of values the coroutine is expected to yield, which is reported by
+    /// [`Coroutine::size_hint`] and the iterators over the coroutine, e.g. so `collect` can
+    /// preallocate.
+    ///
+    /// The hint is not checked, a wrong hint only causes wasted or repeated allocations.
+    pub fn size_hint(mut self, len: usize) -> Self {
+        self.config.size_hint = Some(len);
+        self
+    }
+
+    /// Sets a hook that is called with every value the coroutine yields, before it is returned to
+    /// the caller.
+    pub fn on_yield(mut self, on_yield: impl FnMut(&Y) + 'a) -> Self {
+        self.config.on_yield = Some(Box::new(on_yield));
+        self
+    }
+
+    /// Installs an [`Observer`] that is notified about the lifecycle events of the coroutine.
+    ///
+    /// Multiple observers can be installed, they are notified in the order they were installed.
+    pub fn observer(mut self, observer: impl Observer<Y, T, R> + 'a) -> Self {
+        self.config.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Makes the [`YieldHandle`] implicitly available while the coroutine is resumed, so the body
+    /// can yield with [`yield_value`](crate::yield_value) instead of the handle.
+    pub fn implicit_handle(mut self) -> Self
+    where
+        Y: 'static,
+        R: 'static,
+    {
+        self.config.implicit_handle = Some(|handle| Rc::new(handle.clone_()));
+        self
+    }
+
+    /// Gives the body access to `context` with [`YieldHandle::context`], see
+    /// [`Coroutine::new_with_context`].
+    pub fn context<C>(mut self, context: C) -> Self
+    where
+        C: 'static,
+    {
+        self.config.context = Some(Box::new(context));
+        self
+    }
+
+    /// Lists the coroutine in the [registry](crate::registry) of the current thread while it is
+    /// alive.
+    pub fn register(mut self) -> Self {
+        self.config.register = true;
+        self
+    }
+
+    /// Creates the coroutine from a function that takes the [`YieldHandle`] and the initial value.
+    ///
+    /// See [`Coroutine::new`] for details.
+    #[track_caller]
+    pub fn build<F>(self, f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'a) -> Coroutine<'a, Y, T, R>
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Coroutine::with_config(ExecutorState::new(f), self.config)
+    }
+
+    /// Creates a restartable coroutine from a function that takes the [`YieldHandle`] and the
+    /// initial value.
+    ///
+    /// See [`Coroutine::restartable`] for details.
+    #[track_caller]
+    pub fn build_restartable<F>(
+        self,
+        f: impl Fn(YieldHandle<Y, R>, R) -> F + 'a,
+    ) -> Coroutine<'a, Y, T, R>
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Coroutine::with_config(ExecutorState::restartable(f), self.config)
+    }
+
+    #[track_caller]
+    pub(crate) fn build_executor(
+        self,
+        executor: ExecutorState<'a, Y, T, R>,
+    ) -> Coroutine<'a, Y, T, R> {
+        Coroutine::with_config(executor, self.config)
+    }
+}
+
+impl<Y, T, R> Default for CoroutineBuilder<'_, Y, T, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) struct Config<'a, Y, T, R> {
+    pub(crate) name: Option<String>,
+    pub(crate) spin_policy: SpinPolicy,
+    pub(crate) yield_policy: YieldPolicy,
+    pub(crate) yield_interval: Option<usize>,
+    pub(crate) size_hint: Option<usize>,
+    #[expect(clippy::type_complexity)]
+    pub(crate) on_yield: Option<Box<dyn FnMut(&Y) + 'a>>,
+    pub(crate) observers: Vec<Box<dyn Observer<Y, T, R> + 'a>>,
+    #[expect(clippy::type_complexity)]
+    pub(crate) implicit_handle: Option<fn(&YieldHandle<Y, R>) -> Rc<dyn Any>>,
+    pub(crate) context: Option<Box<dyn Any>>,
+    pub(crate) register: bool,
+}
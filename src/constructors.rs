@@ -0,0 +1,135 @@
+use crate::Generator;
+
+impl<'a, Y> Generator<'a, Y, ()>
+where
+    Y: 'a,
+{
+    /// Creates a generator that completes immediately without yielding.
+    #[track_caller]
+    pub fn empty() -> Self {
+        Self::new(|_, ()| async {})
+    }
+
+    /// Creates a generator that yields `value` once and completes.
+    #[track_caller]
+    pub fn once(value: Y) -> Self {
+        Self::new(|handle, ()| async move { handle.yield_(value).await })
+    }
+
+    /// Creates a generator that endlessly yields the values returned by `f`.
+    #[track_caller]
+    pub fn repeat_with(mut f: impl FnMut() -> Y + 'a) -> Self {
+        Self::new(|handle, ()| async move {
+            loop {
+                handle.yield_(f()).await;
+            }
+        })
+    }
+
+    /// Creates a generator that yields `first` and then the successors computed by `f` from the
+    /// previous value, until either is `None`.
+    #[track_caller]
+    pub fn successors(first: Option<Y>, mut f: impl FnMut(&Y) -> Option<Y> + 'a) -> Self {
+        Self::new(|handle, ()| async move {
+            let mut next = first;
+            while let Some(value) = next {
+                next = f(&value);
+                handle.yield_(value).await;
+            }
+        })
+    }
+
+    /// Creates a generator that yields the values returned by `f` until it returns `None`.
+    #[track_caller]
+    pub fn from_fn(mut f: impl FnMut() -> Option<Y> + 'a) -> Self {
+        Self::new(|handle, ()| async move {
+            while let Some(value) = f() {
+                handle.yield_(value).await;
+            }
+        })
+    }
+
+    /// Creates a generator that lazily yields the values of `iter`.
+    #[track_caller]
+    pub fn from_iterator<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Y>,
+        I::IntoIter: 'a,
+    {
+        let mut iter = iter.into_iter();
+        Self::from_fn(move || iter.next())
+    }
+}
+
+/// Collects the values eagerly, since the iterator is not required to be `'static`. Use
+/// [`Generator::from_iterator`] to yield them lazily.
+impl<'a, Y> FromIterator<Y> for Generator<'a, Y, ()>
+where
+    Y: 'a,
+{
+    #[track_caller]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Y>,
+    {
+        Self::from_iterator(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Generator, State};
+
+    #[test]
+    fn test_empty_and_once() {
+        let mut generator = Generator::<i32, ()>::empty();
+        assert_eq!(generator.resume(), State::Complete(()));
+
+        let mut generator = Generator::once('a');
+        assert_eq!(generator.resume(), State::Yield('a'));
+        assert_eq!(generator.resume(), State::Complete(()));
+    }
+
+    #[test]
+    fn test_repeat_with() {
+        let mut value = 1;
+        let mut generator = Generator::repeat_with(move || {
+            value *= 2;
+            value
+        });
+
+        assert_eq!(generator.resume_n(3, || ()), [2, 4, 8].map(State::Yield));
+    }
+
+    #[test]
+    fn test_successors() {
+        let generator = Generator::successors(Some(1), |value| (*value < 100).then(|| value * 10));
+
+        assert_eq!(
+            generator.collect_with_final::<Vec<_>>(),
+            (vec![1, 10, 100], ())
+        );
+    }
+
+    #[test]
+    fn test_from_fn() {
+        let mut values = vec![1, 2];
+        let generator = Generator::from_fn(move || values.pop());
+
+        assert_eq!(generator.collect_with_final::<Vec<_>>(), (vec![2, 1], ()));
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let generator = (1..=3)
+            .map(|value| value * 2)
+            .collect::<Generator<'_, _, _>>();
+        assert_eq!(
+            generator.collect_with_final::<Vec<_>>(),
+            (vec![2, 4, 6], ())
+        );
+
+        let mut generator = Generator::from_iterator(1..);
+        assert_eq!(generator.resume_n(2, || ()), [1, 2].map(State::Yield));
+    }
+}
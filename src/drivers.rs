@@ -0,0 +1,809 @@
+//! Drivers that repeatedly resume a [`Coroutine`] on behalf of the caller.
+
+use crate::{Consumer, Coroutine, Generator, Resumable, State, Throttled};
+use std::{
+    future::Future,
+    iter::FusedIterator,
+    thread,
+    time::{Duration, Instant},
+};
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
+    /// Runs the coroutine to completion, starting with `resume` and calling `f` for every
+    /// yielded value to obtain the next resume value.
+    pub fn run(mut self, mut resume: R, mut f: impl FnMut(Y) -> R) -> T {
+        loop {
+            match self.resume_with(resume) {
+                State::Yield(value) => resume = f(value),
+                State::Complete(value) => break value,
+            }
+        }
+    }
+
+    /// Runs the coroutine to completion like [`Coroutine::run`], but awaits the future returned by
+    /// `f` to obtain the next resume value.
+    ///
+    /// This allows answering yielded values by performing asynchronous work, e.g. I/O, before
+    /// resuming the coroutine.
+    pub async fn run_async<F>(mut self, mut resume: R, mut f: impl FnMut(Y) -> F) -> T
+    where
+        F: Future<Output = R>,
+    {
+        loop {
+            match self.resume_with(resume) {
+                State::Yield(value) => resume = f(value).await,
+                State::Complete(value) => break value,
+            }
+        }
+    }
+
+    /// Resumes the coroutine once for every value of `resumes`, until it completes or the values
+    /// run out.
+    ///
+    /// Returns all yielded values together with the completion value, which is `None` if the
+    /// resume values ran out before the coroutine completed.
+    pub fn drive_with(&mut self, resumes: impl IntoIterator<Item = R>) -> (Vec<Y>, Option<T>) {
+        let mut yields = Vec::new();
+        for resume in resumes {
+            match self.resume_with(resume) {
+                State::Yield(value) => yields.push(value),
+                State::Complete(value) => return (yields, Some(value)),
+            }
+        }
+        (yields, None)
+    }
+
+    /// Resumes the coroutine at most `n` times, obtaining each resume value from `resume`.
+    ///
+    /// Returns the observed states in order. Stops early if the coroutine completes, in which case
+    /// the last state is [`State::Complete`].
+    pub fn resume_n(&mut self, n: usize, mut resume: impl FnMut() -> R) -> Vec<State<Y, T>> {
+        let mut states = Vec::with_capacity(n);
+        for _ in 0..n {
+            let state = self.resume_with(resume());
+            let complete = state.is_complete();
+            states.push(state);
+            if complete {
+                break;
+            }
+        }
+        states
+    }
+
+    /// Resumes the coroutine, obtaining each resume value from `resume`, until it yields a value
+    /// that matches `predicate` or completes.
+    ///
+    /// Returns the matching value as [`State::Yield`] or the completion value as
+    /// [`State::Complete`]. The values that do not match are dropped.
+    ///
+    /// ```
+    /// use async_coroutine::{Generator, State};
+    ///
+    /// let mut events = Generator::new(|handle, ()| async move {
+    ///     for event in ["tick", "tick", "hit", "tick"] {
+    ///         handle.yield_(event).await;
+    ///     }
+    ///     "end"
+    /// });
+    ///
+    /// assert_eq!(events.resume_until(|| (), |event| *event == "hit"), State::Yield("hit"));
+    /// assert_eq!(events.resume_until(|| (), |event| *event == "hit"), State::Complete("end"));
+    /// ```
+    #[track_caller]
+    pub fn resume_until(
+        &mut self,
+        mut resume: impl FnMut() -> R,
+        mut predicate: impl FnMut(&Y) -> bool,
+    ) -> State<Y, T> {
+        loop {
+            match self.resume_with(resume()) {
+                State::Yield(value) if !predicate(&value) => (),
+                state => break state,
+            }
+        }
+    }
+
+    /// Runs the coroutine to completion, obtaining each resume value from `resume`, and returns
+    /// every yielded value together with the completion value.
+    ///
+    /// ```
+    /// use async_coroutine::Coroutine;
+    ///
+    /// let mut running_sum = Coroutine::new(|handle, mut value: u32| async move {
+    ///     let mut sum = 0;
+    ///     while value > 0 {
+    ///         sum += value;
+    ///         value = handle.yield_(sum).await;
+    ///     }
+    ///     sum
+    /// });
+    ///
+    /// let mut values = [1, 2, 3, 0].into_iter();
+    /// assert_eq!(running_sum.drain(|| values.next().unwrap()), (vec![1, 3, 6], 6));
+    /// ```
+    #[track_caller]
+    pub fn drain(&mut self, mut resume: impl FnMut() -> R) -> (Vec<Y>, T) {
+        let mut yields = Vec::new();
+        loop {
+            match self.resume_with(resume()) {
+                State::Yield(value) => yields.push(value),
+                State::Complete(value) => break (yields, value),
+            }
+        }
+    }
+
+    /// Runs the coroutine to completion, resuming it with clones of `resume`, and returns the
+    /// completion value. The yielded values are dropped.
+    #[track_caller]
+    pub fn finish_with(&mut self, resume: R) -> T
+    where
+        R: Clone,
+    {
+        loop {
+            if let State::Complete(value) = self.resume_with(resume.clone()) {
+                break value;
+            }
+        }
+    }
+
+    /// Runs the coroutine to completion, resuming it with the default resume value, e.g. `()` for
+    /// generators, and returns the completion value. The yielded values are dropped.
+    ///
+    /// ```
+    /// use async_coroutine::Generator;
+    ///
+    /// let mut total = Generator::new(|handle, ()| async move {
+    ///     let mut total = 0;
+    ///     for value in 1..=4 {
+    ///         total += value;
+    ///         handle.yield_(total).await;
+    ///     }
+    ///     total
+    /// });
+    ///
+    /// assert_eq!(total.finish(), 10);
+    /// ```
+    #[track_caller]
+    pub fn finish(&mut self) -> T
+    where
+        R: Default,
+    {
+        loop {
+            if let State::Complete(value) = self.resume_with(R::default()) {
+                break value;
+            }
+        }
+    }
+
+    /// Converts the coroutine into a closure that resumes it with its argument.
+    ///
+    /// This allows handing the coroutine to APIs that expect plain callbacks.
+    pub fn into_fn_mut(mut self) -> impl FnMut(R) -> State<Y, T> {
+        move |resume| self.resume_with(resume)
+    }
+
+    /// Creates a driver that resumes the coroutine at most once per `min_interval`.
+    ///
+    /// The interval is measured between the starts of consecutive resumes. The first resume is
+    /// never throttled.
+    pub fn throttle(self, min_interval: Duration) -> Throttle<'a, Y, T, R> {
+        Throttle {
+            inner: self,
+            min_interval,
+            last: None,
+        }
+    }
+}
+
+impl<'a, Y, T> Generator<'a, Y, T> {
+    /// Returns an iterator over the yielded values that gives access to the completion value via
+    /// [`IterMut::finish`] once it is exhausted.
+    pub fn iter_mut(&mut self) -> IterMut<'_, 'a, Y, T> {
+        IterMut {
+            generator: self,
+            complete: None,
+        }
+    }
+
+    /// Returns an iterator over the exactly `len` values the generator yields before it completes,
+    /// which implements [`ExactSizeIterator`].
+    ///
+    /// ```
+    /// use async_coroutine::Generator;
+    ///
+    /// let squares = Generator::new(|handle, ()| async move {
+    ///     for value in 1..=3 {
+    ///         handle.yield_(value * value).await;
+    ///     }
+    /// });
+    ///
+    /// let mut iter = squares.into_exact_iter(3);
+    /// assert_eq!(iter.len(), 3);
+    /// assert_eq!(iter.by_ref().collect::<Vec<_>>(), [1, 4, 9]);
+    /// iter.finish();
+    /// ```
+    pub fn into_exact_iter(self, len: usize) -> ExactIter<'a, Y, T> {
+        ExactIter {
+            generator: self,
+            len,
+            remaining: len,
+        }
+    }
+
+    /// Calls `f` for every yielded value and returns the completion value.
+    pub fn for_each(mut self, f: impl FnMut(Y)) -> T {
+        let mut iter = self.iter_mut();
+        iter.by_ref().for_each(f);
+        iter.finish()
+    }
+
+    /// Folds every yielded value into an accumulator and returns it together with the completion
+    /// value.
+    pub fn fold<A>(mut self, init: A, f: impl FnMut(A, Y) -> A) -> (A, T) {
+        let mut iter = self.iter_mut();
+        let acc = iter.by_ref().fold(init, f);
+        (acc, iter.finish())
+    }
+
+    /// Collects every yielded value into a collection and returns it together with the completion
+    /// value.
+    pub fn collect_with_final<C>(mut self) -> (C, T)
+    where
+        C: FromIterator<Y>,
+    {
+        let mut iter = self.iter_mut();
+        let collection = iter.by_ref().collect();
+        (collection, iter.finish())
+    }
+
+    /// Splits the yielded values into two collections by mapping each of them to a
+    /// [`Left`](either::Either::Left) or a [`Right`](either::Either::Right), and returns both
+    /// together with the completion value.
+    ///
+    /// ```
+    /// use async_coroutine::Generator;
+    /// use either::Either;
+    ///
+    /// let generator = Generator::new(|handle, ()| async move {
+    ///     for value in 1..=5 {
+    ///         handle.yield_(value).await;
+    ///     }
+    ///     "done"
+    /// });
+    ///
+    /// let (even, odd, complete): (Vec<_>, Vec<_>, _) = generator.partition_map(|value| {
+    ///     if value % 2 == 0 { Either::Left(value) } else { Either::Right(value) }
+    /// });
+    /// assert_eq!((even, odd, complete), (vec![2, 4], vec![1, 3, 5], "done"));
+    /// ```
+    #[cfg(feature = "either")]
+    pub fn partition_map<A, B, CA, CB>(
+        self,
+        mut f: impl FnMut(Y) -> either::Either<A, B>,
+    ) -> (CA, CB, T)
+    where
+        CA: Default + Extend<A>,
+        CB: Default + Extend<B>,
+    {
+        let mut left = CA::default();
+        let mut right = CB::default();
+        let complete = self.for_each(|value| match f(value) {
+            either::Either::Left(value) => left.extend(Some(value)),
+            either::Either::Right(value) => right.extend(Some(value)),
+        });
+        (left, right, complete)
+    }
+}
+
+#[cfg(feature = "either")]
+impl<A, B, T> Generator<'_, either::Either<A, B>, T> {
+    /// Calls `left` for every yielded [`Left`](either::Either::Left) and `right` for every yielded
+    /// [`Right`](either::Either::Right), and returns the completion value.
+    ///
+    /// This is useful to handle two kinds of yields separately, e.g. data and diagnostics.
+    pub fn route(self, mut left: impl FnMut(A), mut right: impl FnMut(B)) -> T {
+        self.for_each(|value| match value {
+            either::Either::Left(value) => left(value),
+            either::Either::Right(value) => right(value),
+        })
+    }
+}
+
+impl<T, R> Consumer<'_, T, R> {
+    /// Resumes the consumer once for every value of `values`, until it completes or the values
+    /// run out.
+    ///
+    /// Returns the completion value if the consumer completed. Does nothing if it has already
+    /// completed before.
+    pub fn feed_all(&mut self, values: impl IntoIterator<Item = R>) -> Option<T> {
+        if self.completed {
+            return None;
+        }
+        self.drive_with(values).1
+    }
+}
+
+/// Feeds the values with [`Consumer::feed_all`], dropping the completion value.
+impl<T, R> Extend<R> for Consumer<'_, T, R> {
+    fn extend<I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = R>,
+    {
+        self.feed_all(values);
+    }
+}
+
+/// A driver that limits how often a coroutine is resumed, created by [`Coroutine::throttle`].
+pub struct Throttle<'a, Y, T, R> {
+    inner: Coroutine<'a, Y, T, R>,
+    min_interval: Duration,
+    last: Option<Instant>,
+}
+
+impl<'a, Y, T, R> Throttle<'a, Y, T, R> {
+    /// Returns the time until the coroutine may be resumed again, which is zero if it may be
+    /// resumed right away.
+    pub fn remaining(&self) -> Duration {
+        match self.last {
+            Some(last) => self.min_interval.saturating_sub(last.elapsed()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Resumes the coroutine if the minimum interval since the previous resume has elapsed,
+    /// otherwise returns [`Throttled`] and drops the resume value.
+    #[track_caller]
+    pub fn try_resume_with(&mut self, resume: R) -> Result<State<Y, T>, Throttled> {
+        let remaining = self.remaining();
+        if !remaining.is_zero() {
+            return Err(Throttled { remaining });
+        }
+        self.last = Some(Instant::now());
+        Ok(self.inner.resume_with(resume))
+    }
+
+    /// Resumes the coroutine, putting the current thread to sleep until the minimum interval since
+    /// the previous resume has elapsed.
+    #[track_caller]
+    pub fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        thread::sleep(self.remaining());
+        self.last = Some(Instant::now());
+        self.inner.resume_with(resume)
+    }
+
+    /// Consumes the driver, returning the underlying coroutine.
+    pub fn into_inner(self) -> Coroutine<'a, Y, T, R> {
+        self.inner
+    }
+}
+
+impl<Y, T> Throttle<'_, Y, T, ()> {
+    /// Resumes the underlying generator if the minimum interval has elapsed, see
+    /// [`Throttle::try_resume_with`].
+    #[track_caller]
+    pub fn try_resume(&mut self) -> Result<State<Y, T>, Throttled> {
+        self.try_resume_with(())
+    }
+
+    /// Resumes the underlying generator, sleeping until the minimum interval has elapsed.
+    #[track_caller]
+    pub fn resume(&mut self) -> State<Y, T> {
+        self.resume_with(())
+    }
+}
+
+impl<Y, T, R> Resumable<R> for Throttle<'_, Y, T, R> {
+    type Yield = Y;
+    type Complete = T;
+
+    fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        Throttle::resume_with(self, resume)
+    }
+}
+
+/// An iterator over the values yielded by a generator, created by [`Generator::iter_mut`].
+pub struct IterMut<'a, 'b, Y, T> {
+    generator: &'a mut Generator<'b, Y, T>,
+    complete: Option<T>,
+}
+
+impl<Y, T> IterMut<'_, '_, Y, T> {
+    /// Returns `true` once the generator has completed, afterwards the iterator only returns
+    /// `None`.
+    pub fn is_terminated(&self) -> bool {
+        self.complete.is_some()
+    }
+
+    /// Returns the completion value of the generator.
+    ///
+    /// If the iterator is not exhausted yet, the remaining values are resumed and dropped first.
+    pub fn finish(mut self) -> T {
+        loop {
+            if let Some(complete) = self.complete.take() {
+                break complete;
+            }
+            self.next();
+        }
+    }
+}
+
+impl<Y, T> Iterator for IterMut<'_, '_, Y, T> {
+    type Item = Y;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.complete.is_some() {
+            return None;
+        }
+
+        match self.generator.resume() {
+            State::Yield(value) => Some(value),
+            State::Complete(value) => {
+                self.complete = Some(value);
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.complete.is_some() {
+            (0, Some(0))
+        } else {
+            self.generator.size_hint()
+        }
+    }
+}
+
+/// An iterator over a generator that yields a known number of values, created by
+/// [`Generator::into_exact_iter`].
+///
+/// Advancing the iterator panics if the generator completes before it yielded the expected number
+/// of values.
+pub struct ExactIter<'a, Y, T> {
+    generator: Generator<'a, Y, T>,
+    len: usize,
+    remaining: usize,
+}
+
+impl<Y, T> ExactIter<'_, Y, T> {
+    /// Resumes the generator to completion and returns the completion value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator is not exhausted yet or the generator yields more values than it
+    /// was supposed to.
+    #[track_caller]
+    pub fn finish(mut self) -> T {
+        assert!(
+            self.remaining == 0,
+            "{} was finished with {} of {} values remaining",
+            self.generator.yield_handle.shared.origin,
+            self.remaining,
+            self.len,
+        );
+        match self.generator.resume() {
+            State::Yield(_) => panic!(
+                "{} yielded more than {} values",
+                self.generator.yield_handle.shared.origin, self.len,
+            ),
+            State::Complete(value) => value,
+        }
+    }
+}
+
+impl<Y, T> Iterator for ExactIter<'_, Y, T> {
+    type Item = Y;
+
+    #[track_caller]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        match self.generator.resume() {
+            State::Yield(value) => {
+                self.remaining -= 1;
+                Some(value)
+            }
+            State::Complete(_) => panic!(
+                "{} completed after {} of {} values",
+                self.generator.yield_handle.shared.origin,
+                self.len - self.remaining,
+                self.len,
+            ),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// The generator is not resumed anymore once it has completed.
+impl<Y, T> FusedIterator for IterMut<'_, '_, Y, T> {}
+
+impl<Y, T> ExactSizeIterator for ExactIter<'_, Y, T> {}
+
+impl<Y, T> FusedIterator for ExactIter<'_, Y, T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Consumer, Coroutine, Generator, State};
+    use std::{
+        cell::RefCell,
+        future::Future,
+        pin::pin,
+        rc::Rc,
+        task::{Context, Poll, Waker},
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn test_iter_mut() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            for value in 1..=4 {
+                handle.yield_(value).await;
+            }
+            "Bye"
+        });
+
+        let mut iter = generator.iter_mut();
+        assert_eq!(iter.by_ref().take(2).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(iter.finish(), "Bye");
+    }
+
+    #[test]
+    fn test_iter_mut_exhausted() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            handle.yield_(2).await;
+            42
+        });
+
+        let mut iter = generator.iter_mut();
+        assert_eq!(iter.by_ref().sum::<i32>(), 3);
+        assert!(iter.is_terminated());
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.finish(), 42);
+        assert!(generator.is_terminated());
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let mut generator = Generator::builder()
+            .size_hint(3)
+            .build(|handle, ()| async move {
+                for value in 0..3 {
+                    handle.yield_(value).await;
+                }
+            });
+
+        let mut iter = generator.iter_mut();
+        assert_eq!(iter.size_hint(), (3, None));
+        iter.next();
+        assert_eq!(iter.size_hint(), (2, None));
+        assert_eq!(iter.by_ref().count(), 2);
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "completed after 2 of 3 values")]
+    fn test_exact_iter_too_short() {
+        let generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            handle.yield_(2).await;
+        });
+
+        let iter = generator.into_exact_iter(3);
+        assert_eq!(iter.len(), 3);
+        let _ = iter.collect::<Vec<_>>(); // This panics
+    }
+
+    #[test]
+    fn test_run() {
+        let co = Coroutine::new(|handle, mut value| async move {
+            while value < 100 {
+                value = handle.yield_(value).await;
+            }
+            value
+        });
+
+        let mut yields = Vec::new();
+        let complete = co.run(1, |value| {
+            yields.push(value);
+            value * 3
+        });
+
+        assert_eq!(yields, [1, 3, 9, 27, 81]);
+        assert_eq!(complete, 243);
+    }
+
+    #[test]
+    fn test_run_async() {
+        let co = Coroutine::new(|handle, _initial| async move {
+            let a = handle.yield_("a").await;
+            let b = handle.yield_("bb").await;
+            a + b
+        });
+
+        let future = pin!(co.run_async(0, |value| async move { value.len() }));
+        let mut context = Context::from_waker(Waker::noop());
+        assert_eq!(future.poll(&mut context), Poll::Ready(3));
+    }
+
+    #[test]
+    fn test_drive_with() {
+        let mut co = Coroutine::new(|handle, mut total| async move {
+            loop {
+                let value = handle.yield_(total).await;
+                if value == 0 {
+                    break total;
+                }
+                total += value;
+            }
+        });
+
+        assert_eq!(co.drive_with([1, 2, 3]), (vec![1, 3, 6], None));
+        assert_eq!(co.drive_with([4, 0, 7]), (vec![10], Some(10)));
+    }
+
+    #[test]
+    fn test_terminal_drivers() {
+        let new = || {
+            Generator::new(|handle, ()| async move {
+                for value in 1..=4 {
+                    handle.yield_(value).await;
+                }
+                "Bye"
+            })
+        };
+
+        let mut yields = Vec::new();
+        assert_eq!(new().for_each(|value| yields.push(value)), "Bye");
+        assert_eq!(yields, [1, 2, 3, 4]);
+
+        assert_eq!(new().fold(0, |acc, value| acc + value), (10, "Bye"));
+        assert_eq!(
+            new().collect_with_final::<Vec<_>>(),
+            (vec![1, 2, 3, 4], "Bye")
+        );
+
+        let mut generator = new();
+        generator.resume();
+        assert_eq!(generator.drain(|| ()), (vec![2, 3, 4], "Bye"));
+        assert_eq!(new().finish(), "Bye");
+
+        let mut co = Coroutine::new(|handle, step: u32| async move {
+            let mut value = 0;
+            while value < 10 {
+                value += handle.yield_(value).await;
+            }
+            value + step
+        });
+        assert_eq!(co.finish_with(3), 15);
+    }
+
+    #[test]
+    fn test_into_fn_mut() {
+        let mut tick = Coroutine::new(|handle, mut total| async move {
+            while total < 5 {
+                total += handle.yield_(total).await;
+            }
+            total
+        })
+        .into_fn_mut();
+
+        assert_eq!(tick(1), State::Yield(1));
+        assert_eq!(tick(2), State::Yield(3));
+        assert_eq!(tick(3), State::Complete(6));
+    }
+
+    #[test]
+    fn test_resume_n() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            for value in 1..=3 {
+                handle.yield_(value).await;
+            }
+            "Bye"
+        });
+
+        assert_eq!(generator.resume_n(0, || ()), []);
+        assert_eq!(
+            generator.resume_n(2, || ()),
+            [State::Yield(1), State::Yield(2)]
+        );
+        assert_eq!(
+            generator.resume_n(5, || ()),
+            [State::Yield(3), State::Complete("Bye")]
+        );
+    }
+
+    #[test]
+    fn test_resume_until() {
+        let mut co = Coroutine::new(|handle, mut value: i32| async move {
+            loop {
+                value = handle.yield_(value * 2).await;
+                if value < 0 {
+                    break value;
+                }
+            }
+        });
+
+        let mut resumes = [1, 2, 3, 4, -1].into_iter();
+        let mut next = || resumes.next().unwrap();
+        assert_eq!(
+            co.resume_until(&mut next, |value| *value > 4),
+            State::Yield(6)
+        );
+        assert_eq!(
+            co.resume_until(&mut next, |value| *value > 10),
+            State::Complete(-1)
+        );
+    }
+
+    #[test]
+    fn test_feed_all_and_extend() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut consumer = Consumer::new({
+            let received = Rc::clone(&received);
+            move |handle, mut value| async move {
+                while value != 0 {
+                    received.borrow_mut().push(value);
+                    value = handle.recv().await;
+                }
+                received.borrow().len()
+            }
+        });
+
+        consumer.extend([1, 2]);
+        assert_eq!(consumer.feed_all([3, 0, 4]), Some(3));
+        consumer.extend([5]);
+        assert_eq!(consumer.feed_all([6]), None);
+        assert_eq!(*received.borrow(), [1, 2, 3]);
+    }
+
+    #[cfg(feature = "either")]
+    #[test]
+    fn test_partition() {
+        use either::Either;
+
+        let new = || {
+            Generator::new(|handle, ()| async move {
+                handle.yield_(Either::Left(1)).await;
+                handle.yield_(Either::Right("warning")).await;
+                handle.yield_(Either::Left(2)).await;
+                "Bye"
+            })
+        };
+
+        let (data, diagnostics, complete): (Vec<_>, Vec<_>, _) = new().partition_map(|value| value);
+        assert_eq!(data, [1, 2]);
+        assert_eq!(diagnostics, ["warning"]);
+        assert_eq!(complete, "Bye");
+
+        let mut data = 0;
+        let mut diagnostics = Vec::new();
+        let complete = new().route(|value| data += value, |value| diagnostics.push(value));
+        assert_eq!((data, diagnostics, complete), (3, vec!["warning"], "Bye"));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Generator::new(|handle, ()| async move {
+            for value in 1..=3 {
+                handle.yield_(value).await;
+            }
+        })
+        .throttle(Duration::from_millis(20));
+
+        let start = Instant::now();
+        assert_eq!(throttle.try_resume(), Ok(State::Yield(1)));
+        let throttled = throttle.try_resume().unwrap_err();
+        assert!(throttled.remaining() > Duration::ZERO);
+        assert!(throttled.remaining() <= Duration::from_millis(20));
+
+        assert_eq!(throttle.resume(), State::Yield(2));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(throttle.resume(), State::Yield(3));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+}
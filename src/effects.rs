@@ -0,0 +1,223 @@
+//! Algebraic-effects-style dependency injection with coroutines.
+//!
+//! The body of an [`Effectful`] coroutine performs [`Effect`]s with [`perform`], which yields the
+//! effect and receives back its output. The driver answers the effects with a [`Handlers`]
+//! registry, so the same body can run against real implementations in production and against
+//! fakes in tests.
+//!
+//! ```
+//! use async_coroutine::{Coroutine, effects::{Effect, Handlers, perform}};
+//!
+//! struct ReadEnv(&'static str);
+//!
+//! impl Effect for ReadEnv {
+//!     type Output = Option<String>;
+//! }
+//!
+//! let co = Coroutine::new(|handle, _start| async move {
+//!     let user = perform(&handle, ReadEnv("USER")).await;
+//!     format!("Hello {}", user.unwrap_or_default())
+//! });
+//!
+//! let mut handlers = Handlers::new().on(|ReadEnv(_)| Some("Ferris".to_string()));
+//! assert_eq!(handlers.run(co), "Hello Ferris");
+//! ```
+
+use crate::{Coroutine, YieldHandle};
+use std::{
+    any::{Any, TypeId, type_name},
+    collections::HashMap,
+    fmt,
+};
+
+type Erased = Box<dyn Any>;
+
+/// An effect that can be performed by an [`Effectful`] coroutine.
+pub trait Effect: 'static {
+    /// The value the effect is answered with.
+    type Output: 'static;
+}
+
+/// A coroutine that performs effects, started with an empty resume value by [`Handlers::run`].
+pub type Effectful<T> = Coroutine<'static, Performed, T, Erased>;
+
+/// The handle of an [`Effectful`] coroutine.
+pub type EffectHandle = YieldHandle<Performed, Erased>;
+
+/// An effect yielded by [`perform`].
+pub struct Performed {
+    effect: Erased,
+    type_name: &'static str,
+}
+
+impl Performed {
+    /// Returns the type name of the effect.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns `true` if the effect is of type `E`.
+    pub fn is<E>(&self) -> bool
+    where
+        E: Effect,
+    {
+        self.effect.is::<E>()
+    }
+
+    /// Returns the effect if it is of type `E`.
+    pub fn downcast<E>(self) -> Result<E, Self>
+    where
+        E: Effect,
+    {
+        match self.effect.downcast::<E>() {
+            Ok(effect) => Ok(*effect),
+            Err(effect) => Err(Self {
+                effect,
+                type_name: self.type_name,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for Performed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Performed").field(&self.type_name).finish()
+    }
+}
+
+/// Performs an effect by yielding it and returns the output it is answered with.
+///
+/// # Panics
+///
+/// Panics if the coroutine is resumed with a value that is not of type `E::Output`.
+pub async fn perform<E>(handle: &EffectHandle, effect: E) -> E::Output
+where
+    E: Effect,
+{
+    let performed = Performed {
+        effect: Box::new(effect),
+        type_name: type_name::<E>(),
+    };
+    let output = handle.yield_(performed).await;
+    *output.downcast::<E::Output>().unwrap_or_else(|_| {
+        panic!(
+            "effect {} was answered with a value of the wrong type",
+            type_name::<E>()
+        )
+    })
+}
+
+/// A registry of handlers that answer the effects of [`Effectful`] coroutines.
+#[derive(Default)]
+pub struct Handlers {
+    handlers: HashMap<TypeId, Box<dyn FnMut(Erased) -> Erased>>,
+}
+
+impl Handlers {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the handler for effects of type `E`, replacing the previous one.
+    pub fn on<E>(mut self, mut handler: impl FnMut(E) -> E::Output + 'static) -> Self
+    where
+        E: Effect,
+    {
+        self.handlers.insert(
+            TypeId::of::<E>(),
+            Box::new(move |effect| Box::new(handler(*effect.downcast::<E>().unwrap()))),
+        );
+        self
+    }
+
+    /// Returns `true` if a handler for effects of type `E` is registered.
+    pub fn handles<E>(&self) -> bool
+    where
+        E: Effect,
+    {
+        self.handlers.contains_key(&TypeId::of::<E>())
+    }
+
+    /// Answers a performed effect with its handler.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no handler for the type of the effect is registered.
+    pub fn handle(&mut self, performed: Performed) -> Erased {
+        let handler = self
+            .handlers
+            .get_mut(&(*performed.effect).type_id())
+            .unwrap_or_else(|| panic!("no handler for effect {}", performed.type_name));
+        handler(performed.effect)
+    }
+
+    /// Runs the coroutine to completion, answering every effect with its handler.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine performs an effect without a handler.
+    pub fn run<T>(&mut self, coroutine: Effectful<T>) -> T {
+        coroutine.run(Box::new(()), |performed| self.handle(performed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Effect, Effectful, Handlers, perform};
+    use crate::{Coroutine, State};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Debug)]
+    struct Log(String);
+
+    impl Effect for Log {
+        type Output = ();
+    }
+
+    #[derive(Debug)]
+    struct Random;
+
+    impl Effect for Random {
+        type Output = u32;
+    }
+
+    fn roll() -> Effectful<u32> {
+        Coroutine::new(|handle, _start| async move {
+            let value = perform(&handle, Random).await % 6 + 1;
+            perform(&handle, Log(format!("rolled {value}"))).await;
+            value
+        })
+    }
+
+    #[test]
+    fn test_handlers() {
+        let logs = Rc::new(RefCell::new(Vec::new()));
+        let mut handlers = Handlers::new().on(|Random| 9).on({
+            let logs = Rc::clone(&logs);
+            move |Log(message)| logs.borrow_mut().push(message)
+        });
+
+        assert!(handlers.handles::<Log>());
+        assert_eq!(handlers.run(roll()), 4);
+        assert_eq!(*logs.borrow(), ["rolled 4"]);
+    }
+
+    #[test]
+    fn test_performed() {
+        let mut co = roll();
+        let State::Yield(performed) = co.resume_with(Box::new(())) else {
+            panic!("no effect was performed");
+        };
+        assert!(performed.is::<Random>());
+        assert!(performed.type_name().ends_with("Random"));
+        let performed = performed.downcast::<Log>().unwrap_err();
+        assert!(performed.downcast::<Random>().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "no handler for effect")]
+    fn test_unhandled() {
+        Handlers::new().on(|Random| 1).run(roll());
+    }
+}
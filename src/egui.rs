@@ -0,0 +1,219 @@
+//! Multi-step dialogs for egui written as coroutines.
+//!
+//! A [`WizardCoroutine`] yields a [`Page`] describing what to draw and is resumed with the
+//! [`Response`] of the user once they click one of the buttons of the page. A [`Wizard`] draws the
+//! current page every frame, so dialogs can be written as straight-line code instead of state
+//! machines.
+
+use crate::{Coroutine, State};
+use ::egui::{TextEdit, Ui};
+
+/// A coroutine that describes the pages of a wizard and completes with its result.
+///
+/// It is started with [`Response::default`].
+pub type WizardCoroutine<T> = Coroutine<'static, Page, T, Response>;
+
+/// The description of a page of a wizard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page {
+    widgets: Vec<Widget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Widget {
+    Heading(String),
+    Label(String),
+    TextInput { hint: String, initial: String },
+    Button(String),
+}
+
+impl Page {
+    /// Creates an empty page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a heading.
+    pub fn heading(mut self, text: impl Into<String>) -> Self {
+        self.widgets.push(Widget::Heading(text.into()));
+        self
+    }
+
+    /// Adds a label.
+    pub fn label(mut self, text: impl Into<String>) -> Self {
+        self.widgets.push(Widget::Label(text.into()));
+        self
+    }
+
+    /// Adds a single line text input, whose value is reported in [`Response::inputs`].
+    pub fn text_input(mut self, hint: impl Into<String>, initial: impl Into<String>) -> Self {
+        self.widgets.push(Widget::TextInput {
+            hint: hint.into(),
+            initial: initial.into(),
+        });
+        self
+    }
+
+    /// Adds a button, clicking it resumes the coroutine.
+    pub fn button(mut self, text: impl Into<String>) -> Self {
+        self.widgets.push(Widget::Button(text.into()));
+        self
+    }
+}
+
+/// The response of the user to a [`Page`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Response {
+    /// The text of the clicked button.
+    pub button: String,
+    /// The values of the text inputs, in the order they were added to the page.
+    pub inputs: Vec<String>,
+}
+
+/// Draws the pages of a [`WizardCoroutine`] and resumes it with the responses of the user.
+pub struct Wizard<T> {
+    coroutine: WizardCoroutine<T>,
+    page: Option<Page>,
+    inputs: Vec<String>,
+    done: bool,
+}
+
+impl<T> Wizard<T> {
+    /// Creates a wizard, the coroutine is started once the first page is needed.
+    pub fn new(coroutine: WizardCoroutine<T>) -> Self {
+        Self {
+            coroutine,
+            page: None,
+            inputs: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Returns `true` if the coroutine has completed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns the current page, starting the coroutine if necessary.
+    ///
+    /// Returns `None` once the coroutine has completed.
+    pub fn page(&mut self) -> Option<&Page> {
+        if self.page.is_none() && !self.done {
+            self.respond(Response::default());
+        }
+        self.page.as_ref()
+    }
+
+    /// Resumes the coroutine with a response to the current page.
+    ///
+    /// Returns the completion value if the coroutine completed, the next page is available via
+    /// [`Wizard::page`] otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine has already completed.
+    pub fn respond(&mut self, response: Response) -> Option<T> {
+        match self.coroutine.resume_with(response) {
+            State::Yield(page) => {
+                self.inputs = page
+                    .widgets
+                    .iter()
+                    .filter_map(|widget| match widget {
+                        Widget::TextInput { initial, .. } => Some(initial.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                self.page = Some(page);
+                None
+            }
+            State::Complete(value) => {
+                self.page = None;
+                self.done = true;
+                Some(value)
+            }
+        }
+    }
+
+    /// Draws the current page and resumes the coroutine if a button was clicked.
+    ///
+    /// Returns the completion value in the frame the coroutine completes. Afterwards nothing is
+    /// drawn.
+    pub fn show(&mut self, ui: &mut Ui) -> Option<T> {
+        self.page()?;
+        let page = self.page.as_ref().unwrap();
+
+        let mut inputs = self.inputs.iter_mut();
+        let mut clicked = None;
+        for widget in &page.widgets {
+            match widget {
+                Widget::Heading(text) => {
+                    ui.heading(text);
+                }
+                Widget::Label(text) => {
+                    ui.label(text);
+                }
+                Widget::TextInput { hint, .. } => {
+                    let input = inputs.next().unwrap();
+                    ui.add(TextEdit::singleline(input).hint_text(hint));
+                }
+                Widget::Button(text) => {
+                    if ui.button(text).clicked() {
+                        clicked = Some(text.clone());
+                    }
+                }
+            }
+        }
+
+        let button = clicked?;
+        let inputs = std::mem::take(&mut self.inputs);
+        self.respond(Response { button, inputs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Page, Response, Wizard};
+    use crate::Coroutine;
+    use ::egui::{Context, RawInput};
+
+    #[test]
+    fn test_wizard() {
+        let mut wizard = Wizard::new(Coroutine::new(|handle, _start: Response| async move {
+            loop {
+                let response = handle
+                    .yield_(Page::new().text_input("Name", "").button("Next"))
+                    .await;
+                let name = &response.inputs[0];
+                if !name.is_empty() {
+                    let page = Page::new().label(format!("Hello {name}")).button("Done");
+                    handle.yield_(page).await;
+                    break name.clone();
+                }
+            }
+        }));
+
+        let ctx = Context::default();
+        let output = ctx.run_ui(RawInput::default(), |ui| assert_eq!(wizard.show(ui), None));
+        output.drop_without_applying_deltas();
+        assert_eq!(
+            wizard.page(),
+            Some(&Page::new().text_input("Name", "").button("Next"))
+        );
+
+        let mut next = |inputs: &[&str]| {
+            wizard.respond(Response {
+                button: "Next".into(),
+                inputs: inputs.iter().map(|input| input.to_string()).collect(),
+            })
+        };
+        assert_eq!(next(&[""]), None);
+        assert_eq!(next(&["Ferris"]), None);
+        assert_eq!(
+            wizard.page(),
+            Some(&Page::new().label("Hello Ferris").button("Done"))
+        );
+        assert_eq!(wizard.respond(Response::default()), Some("Ferris".into()));
+        assert!(wizard.is_done());
+        assert_eq!(wizard.page(), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Integration with embassy for cooperative state machines on embedded targets.
+//!
+//! [`Coroutine::resume_async`] polls the body with the waker of the embassy task that awaits the
+//! resume, so the body can await embassy futures like [`Timer`] and the resume completes once they
+//! fire. [`Generator::run_every`] resumes a generator periodically with a [`Ticker`].
+//!
+//! ```
+//! use async_coroutine::{Generator, State};
+//! use embassy_time::{Duration, Timer};
+//!
+//! let mut blink = Generator::new(|handle, ()| async move {
+//!     for on in [true, false] {
+//!         Timer::after(Duration::from_millis(1)).await;
+//!         handle.yield_(on).await;
+//!     }
+//! });
+//!
+//! embassy_futures::block_on(async {
+//!     assert_eq!(blink.resume_async(()).await, State::Yield(true));
+//!     assert_eq!(blink.resume_async(()).await, State::Yield(false));
+//!     assert_eq!(blink.resume_async(()).await, State::Complete(()));
+//! });
+//! ```
+
+use crate::{Coroutine, Generator, State};
+use embassy_time::{Duration, Ticker};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
+    /// Resumes the coroutine with a value of type `R` from an async task.
+    ///
+    /// Unlike [`Coroutine::resume_with`], the body is not polled in a loop while it awaits other
+    /// futures. The returned future is pending until they wake the task, which makes it possible to
+    /// await timers and peripherals inside the body. Futures that are pending without waking the
+    /// task stall the resume.
+    ///
+    /// If the returned future is dropped before it completes, the resume stays in progress and can
+    /// be continued with [`Coroutine::poll_with_budget`].
+    #[track_caller]
+    pub fn resume_async(&mut self, resume: R) -> ResumeAsync<'_, 'a, Y, T, R> {
+        self.start_resume(resume);
+        ResumeAsync { coroutine: self }
+    }
+}
+
+impl<Y, T> Generator<'_, Y, T> {
+    /// Resumes the generator once every `period` and passes the yielded values to `f`, until the
+    /// generator completes.
+    ///
+    /// The periods are measured with a [`Ticker`], so they do not drift if a resume takes longer
+    /// than expected.
+    pub async fn run_every(&mut self, period: Duration, mut f: impl FnMut(Y)) -> T {
+        let mut ticker = Ticker::every(period);
+        loop {
+            match self.resume_async(()).await {
+                State::Yield(value) => f(value),
+                State::Complete(value) => break value,
+            }
+            ticker.next().await;
+        }
+    }
+}
+
+/// The future returned by [`Coroutine::resume_async`].
+#[must_use = "the resume is only polled when the future is awaited"]
+pub struct ResumeAsync<'a, 'b, Y, T, R> {
+    coroutine: &'a mut Coroutine<'b, Y, T, R>,
+}
+
+impl<Y, T, R> Future for ResumeAsync<'_, '_, Y, T, R> {
+    type Output = State<Y, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.get_mut().coroutine.poll_step(Some(cx.waker())) {
+            Some(state) => Poll::Ready(state),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Coroutine, Generator};
+    use embassy_time::{Duration, Instant, Timer};
+
+    #[test]
+    fn test_resume_async_timer() {
+        let mut co = Coroutine::new(|handle, mut delay: u64| async move {
+            loop {
+                let start = Instant::now();
+                Timer::after(Duration::from_millis(delay)).await;
+                handle.checkpoint().await;
+                delay = handle.yield_(start.elapsed()).await;
+            }
+        });
+
+        embassy_futures::block_on(async {
+            let elapsed = co.resume_async(5).await.unwrap_yield();
+            assert!(elapsed >= Duration::from_millis(5));
+            let elapsed = co.resume_async(1).await.unwrap_yield();
+            assert!(elapsed >= Duration::from_millis(1));
+        });
+    }
+
+    #[test]
+    fn test_run_every() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            for value in 0..3 {
+                handle.yield_(value).await;
+            }
+            "done"
+        });
+
+        let mut values = Vec::new();
+        let start = Instant::now();
+        let complete = embassy_futures::block_on(
+            generator.run_every(Duration::from_millis(2), |value| values.push(value)),
+        );
+        assert_eq!(complete, "done");
+        assert_eq!(values, [0, 1, 2]);
+        assert!(start.elapsed() >= Duration::from_millis(6));
+    }
+}
@@ -0,0 +1,89 @@
+use std::{error::Error, fmt, time::Duration};
+
+/// The error returned by budgeted resumes if the body was polled too often without yielding or
+/// completing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BudgetExceeded;
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("poll budget exceeded without yielding or completing")
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+/// The error returned by timed resumes if the deadline passed before the body yielded or
+/// completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timed out without yielding or completing")
+    }
+}
+
+impl Error for TimedOut {}
+
+/// The error returned when values are sent to a coroutine that has already completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Completed;
+
+impl fmt::Display for Completed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("coroutine has already completed")
+    }
+}
+
+impl Error for Completed {}
+
+/// The error returned when a coroutine is driven after it was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("coroutine was cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// The error returned by [`Throttle::try_resume_with`](crate::drivers::Throttle::try_resume_with)
+/// if the minimum interval since the previous resume has not elapsed yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled {
+    pub(crate) remaining: Duration,
+}
+
+impl Throttled {
+    /// Returns the time until the coroutine may be resumed again.
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "resumed too early, {:?} remaining", self.remaining)
+    }
+}
+
+impl Error for Throttled {}
+
+// `defmt` can not format a `Duration`
+#[cfg(feature = "defmt")]
+impl defmt::Format for Throttled {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "Throttled {{ remaining: {=u64} us }}",
+            self.remaining.as_micros().try_into().unwrap_or(u64::MAX),
+        );
+    }
+}
@@ -1,51 +1,157 @@
+use crate::{YieldHandle, inline::InlineBody};
 use std::{
     future::Future,
+    mem::MaybeUninit,
     pin::Pin,
-    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    ptr,
+    task::{Context, Poll, Waker},
 };
 
-fn dummy_raw_waker() -> RawWaker {
-    fn no_op(_: *const ()) {}
-    fn clone(_: *const ()) -> RawWaker {
-        dummy_raw_waker()
-    }
-    let vtable = &RawWakerVTable::new(clone, no_op, no_op, no_op);
-    RawWaker::new(std::ptr::null::<()>(), vtable)
+/// The future of a body, which never moves.
+pub enum Body<'a, T> {
+    Boxed(Pin<Box<dyn Future<Output = T> + 'a>>),
+    Inline(InlineBody<'a, T>),
+    #[cfg(feature = "allocator-api2")]
+    InAlloc(crate::allocator::AllocBody<'a, T>),
 }
 
-fn dummy_waker() -> Waker {
-    unsafe { Waker::from_raw(dummy_raw_waker()) }
+impl<T> Body<'_, T> {
+    fn poll(&mut self, context: &mut Context<'_>) -> Poll<T> {
+        match self {
+            Body::Boxed(future) => future.as_mut().poll(context),
+            Body::Inline(future) => future.poll(context),
+            #[cfg(feature = "allocator-api2")]
+            Body::InAlloc(future) => future.poll(context),
+        }
+    }
 }
 
-struct Task<R> {
-    future: Pin<Box<dyn Future<Output = R>>>,
+struct Task<'a, R> {
+    // The coroutine owning the task is `Unpin` and may be moved between resumes, so the body is
+    // either on the heap or in the buffer of a pinned `InlineCoroutine`
+    body: Body<'a, R>,
 }
 
-impl<R> Task<R> {
-    fn new(future: Pin<Box<dyn Future<Output = R>>>) -> Self {
-        Task { future }
+impl<'a, R> Task<'a, R> {
+    fn new(body: Body<'a, R>) -> Self {
+        Task { body }
     }
 
     fn poll(&mut self, context: &mut Context<'_>) -> Poll<R> {
-        self.future.as_mut().poll(context)
+        self.body.poll(context)
     }
 }
 
 /// Single-threaded single-task polling-based executor.
-pub struct Executor<T> {
-    task: Task<T>,
+pub struct Executor<'a, T> {
+    task: Task<'a, T>,
 }
 
-impl<T> Executor<T> {
-    pub fn new(future: Pin<Box<dyn Future<Output = T>>>) -> Self {
+impl<'a, T> Executor<'a, T> {
+    pub fn new(body: Body<'a, T>) -> Self {
         Self {
-            task: Task::new(future),
+            task: Task::new(body),
         }
     }
 
-    pub fn poll(&mut self) -> Poll<T> {
-        let waker = dummy_waker();
-        let mut context = Context::from_waker(&waker);
+    pub fn poll(&mut self, waker: &Waker) -> Poll<T> {
+        let mut context = Context::from_waker(waker);
         self.task.poll(&mut context)
     }
 }
+
+/// Creates the bodies of a restartable coroutine.
+pub trait Restart<'a, Y, T, R> {
+    fn start(
+        &mut self,
+        handle: YieldHandle<Y, R>,
+        initial_value: R,
+    ) -> Pin<Box<dyn Future<Output = T> + 'a>>;
+
+    /// Drops the body of `executor`, but keeps its allocation for the next `start`.
+    fn recycle(&mut self, executor: Executor<'a, T>);
+}
+
+pub struct Restartable<G, F> {
+    f: G,
+    spare: Option<Box<MaybeUninit<F>>>,
+}
+
+impl<G, F> Restartable<G, F> {
+    pub fn new(f: G) -> Self {
+        Self { f, spare: None }
+    }
+}
+
+impl<'a, Y, T, R, G, F> Restart<'a, Y, T, R> for Restartable<G, F>
+where
+    G: Fn(YieldHandle<Y, R>, R) -> F,
+    F: Future<Output = T> + 'a,
+{
+    fn start(
+        &mut self,
+        handle: YieldHandle<Y, R>,
+        initial_value: R,
+    ) -> Pin<Box<dyn Future<Output = T> + 'a>> {
+        let future = (self.f)(handle, initial_value);
+        let future: Box<F> = match self.spare.take() {
+            Some(spare) => Box::write(spare, future),
+            None => Box::new(future),
+        };
+        Box::into_pin(future)
+    }
+
+    fn recycle(&mut self, executor: Executor<'a, T>) {
+        let Body::Boxed(future) = executor.task.body else {
+            unreachable!("the bodies of restartable coroutines are boxed");
+        };
+        // SAFETY: The body is dropped in place before its memory is reused. It was created by
+        // `start`, because restartable coroutines create every body with it, so it is an `F`.
+        unsafe {
+            let future = Box::into_raw(Pin::into_inner_unchecked(future)).cast::<F>();
+            ptr::drop_in_place(future);
+            self.spare = Some(Box::from_raw(future.cast::<MaybeUninit<F>>()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Body;
+    use crate::{Coroutine, State};
+    use std::{future::Future, rc::Rc};
+
+    fn body_ptr<Y, T, R>(co: &Coroutine<'_, Y, T, R>) -> *const () {
+        let executor = co.executor.executor.as_ref().unwrap();
+        let Body::Boxed(future) = &executor.task.body else {
+            unreachable!();
+        };
+        let future: &dyn Future<Output = T> = &**future;
+        (future as *const dyn Future<Output = T>).cast()
+    }
+
+    #[test]
+    fn test_reset_reuses_body() {
+        let alive = Rc::new(());
+        let mut co = Coroutine::restartable({
+            let alive = Rc::clone(&alive);
+            move |handle, start: i32| {
+                let alive = Rc::clone(&alive);
+                async move {
+                    let _alive = alive;
+                    start + handle.yield_(start).await
+                }
+            }
+        });
+
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        let body = body_ptr(&co);
+        assert_eq!(Rc::strong_count(&alive), 3);
+
+        co.reset();
+        assert_eq!(Rc::strong_count(&alive), 2);
+        assert_eq!(co.resume_with(2), State::Yield(2));
+        assert_eq!(body_ptr(&co), body);
+        assert_eq!(co.resume_with(3), State::Complete(5));
+    }
+}
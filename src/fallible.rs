@@ -0,0 +1,250 @@
+// Coroutines whose body can fail, i.e. that complete with a `Result`
+
+use crate::{Coroutine, State, YieldHandle};
+
+/// A coroutine whose body completes with either a value of type `T` or an error of type `E`.
+///
+/// The body returns a `Result`, so errors can be propagated with `?`. The `try_*` methods report
+/// the error as [`TryState::Error`] instead of a completion.
+pub type TryCoroutine<'a, Y, T, E, R> = Coroutine<'a, Y, Result<T, E>, R>;
+
+/// A generator whose body completes with either a value of type `T` or an error of type `E`, see
+/// [`TryCoroutine`].
+///
+/// ```
+/// use async_coroutine::{TryGenerator, TryState};
+///
+/// let mut numbers: TryGenerator<u32, usize, std::num::ParseIntError> =
+///     TryGenerator::new(|handle, ()| async move {
+///         let mut count = 0;
+///         for text in ["1", "2", "three"] {
+///             handle.yield_(text.parse()?).await;
+///             count += 1;
+///         }
+///         Ok(count)
+///     });
+///
+/// assert_eq!(numbers.try_resume(), TryState::Yield(1));
+/// assert_eq!(numbers.try_resume(), TryState::Yield(2));
+/// assert!(numbers.try_resume().is_error());
+/// ```
+pub type TryGenerator<'a, Y, T, E> = TryCoroutine<'a, Y, T, E, ()>;
+
+/// Represents the state of a [`TryCoroutine`], which can either yield a value of type `Y`,
+/// complete with a value of type `T` or fail with an error of type `E`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TryState<Y, T, E> {
+    /// The coroutine yielded a value of type `Y`.
+    Yield(Y),
+    /// The coroutine completed with a value of type `T`.
+    Complete(T),
+    /// The coroutine failed with an error of type `E`.
+    Error(E),
+}
+
+impl<Y, T, E> TryState<Y, T, E> {
+    /// Returns `true` if the state is [`Yield`](TryState::Yield).
+    pub fn is_yield(&self) -> bool {
+        matches!(self, Self::Yield(..))
+    }
+
+    /// Returns `true` if the state is [`Complete`](TryState::Complete).
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(..))
+    }
+
+    /// Returns `true` if the state is [`Error`](TryState::Error).
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error(..))
+    }
+
+    /// Converts the state into `Some` with the yielded value if it is
+    /// [`Yield`](TryState::Yield), otherwise `None`.
+    pub fn into_yield(self) -> Option<Y> {
+        if let Self::Yield(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Converts the state into `Some` with the error if it is [`Error`](TryState::Error),
+    /// otherwise `None`.
+    pub fn into_error(self) -> Option<E> {
+        if let Self::Error(e) = self {
+            Some(e)
+        } else {
+            None
+        }
+    }
+
+    /// Moves the error out of the state, so it can be propagated with `?`.
+    pub fn transpose(self) -> Result<State<Y, T>, E> {
+        match self {
+            Self::Yield(value) => Ok(State::Yield(value)),
+            Self::Complete(value) => Ok(State::Complete(value)),
+            Self::Error(error) => Err(error),
+        }
+    }
+}
+
+impl<Y, T, E> From<State<Y, Result<T, E>>> for TryState<Y, T, E> {
+    fn from(state: State<Y, Result<T, E>>) -> Self {
+        match state {
+            State::Yield(value) => Self::Yield(value),
+            State::Complete(Ok(value)) => Self::Complete(value),
+            State::Complete(Err(error)) => Self::Error(error),
+        }
+    }
+}
+
+impl<Y, T, E, R> TryCoroutine<'_, Y, T, E, R> {
+    /// Resumes the coroutine with a value of type `R`, reporting an error of the body as
+    /// [`TryState::Error`].
+    #[track_caller]
+    pub fn try_resume_with(&mut self, resume: R) -> TryState<Y, T, E> {
+        self.resume_with(resume).into()
+    }
+}
+
+impl<Y, T, E> TryGenerator<'_, Y, T, E> {
+    /// Resumes the underlying generator, see [`TryCoroutine::try_resume_with`].
+    #[track_caller]
+    pub fn try_resume(&mut self) -> TryState<Y, T, E> {
+        self.try_resume_with(())
+    }
+
+    /// Collects every yielded value into a collection and returns it together with the completion
+    /// value, or the error if the body failed. The values yielded before the error are dropped.
+    pub fn try_collect<C>(self) -> Result<(C, T), E>
+    where
+        C: FromIterator<Y>,
+    {
+        let (collection, complete) = self.collect_with_final();
+        complete.map(|complete| (collection, complete))
+    }
+}
+
+impl<Y, R> YieldHandle<Y, R> {
+    /// Yields every value of `values` until the first error, which is returned without yielding.
+    ///
+    /// Returns the resume value of the last yield, or `None` if there were no values. In the body
+    /// of a [`TryCoroutine`], the error ends the whole coroutine with `?`.
+    ///
+    /// ```
+    /// use async_coroutine::{TryGenerator, TryState};
+    ///
+    /// let mut rows: TryGenerator<u32, (), String> = TryGenerator::new(|handle, ()| async move {
+    ///     let parsed = ["1", "2", "x", "4"].map(|row| row.parse().map_err(|_| format!("bad {row}")));
+    ///     handle.try_yield_all(parsed).await?;
+    ///     Ok(())
+    /// });
+    ///
+    /// assert_eq!(rows.try_resume(), TryState::Yield(1));
+    /// assert_eq!(rows.try_resume(), TryState::Yield(2));
+    /// assert_eq!(rows.try_resume(), TryState::Error("bad x".to_string()));
+    /// ```
+    pub async fn try_yield_all<E>(
+        &self,
+        values: impl IntoIterator<Item = Result<Y, E>>,
+    ) -> Result<Option<R>, E> {
+        let mut resume = None;
+        for value in values {
+            resume = Some(self.yield_(value?).await);
+        }
+        Ok(resume)
+    }
+}
+
+impl<Y, E, R> YieldHandle<Result<Y, E>, R> {
+    /// Yields `Ok(value)` and receives back the resume value.
+    pub async fn yield_ok(&self, value: Y) -> R {
+        self.yield_(Ok(value)).await
+    }
+
+    /// Yields `Err(error)` and receives back the resume value.
+    pub async fn yield_err(&self, error: E) -> R {
+        self.yield_(Err(error)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TryGenerator, TryState};
+    use crate::{Coroutine, Generator, State};
+
+    fn countdown(from: i32) -> TryGenerator<'static, i32, &'static str, String> {
+        TryGenerator::new(move |handle, ()| async move {
+            for value in (0..=from).rev() {
+                if value == 13 {
+                    return Err(format!("unlucky {value}"));
+                }
+                handle.yield_(value).await;
+            }
+            Ok("liftoff")
+        })
+    }
+
+    #[test]
+    fn test_try_resume() {
+        let mut co = Coroutine::new(|handle, mut value: i32| async move {
+            while value != 0 {
+                value = handle.yield_(value).await;
+            }
+            Err::<(), _>("zero")
+        });
+
+        assert_eq!(co.try_resume_with(1), TryState::Yield(1));
+        assert_eq!(co.try_resume_with(0), TryState::Error("zero"));
+        assert_eq!(
+            TryState::<i32, (), &str>::Yield(1).transpose(),
+            Ok(State::Yield(1))
+        );
+    }
+
+    #[test]
+    fn test_yield_ok_and_err() {
+        let generator = Generator::new(|handle, ()| async move {
+            for text in ["1", "x", "3"] {
+                match text.parse::<u32>() {
+                    Ok(value) => handle.yield_ok(value).await,
+                    Err(_) => handle.yield_err(text).await,
+                }
+            }
+        });
+
+        assert_eq!(
+            generator.collect_with_final::<Vec<_>>().0,
+            [Ok(1), Err("x"), Ok(3)]
+        );
+    }
+
+    #[test]
+    fn test_try_yield_all() {
+        let mut co: TryGenerator<'_, i32, Option<()>, &str> =
+            TryGenerator::new(|handle, ()| async move {
+                let last = handle.try_yield_all([Ok::<_, &str>(1), Ok(2)]).await?;
+                handle.try_yield_all([Ok(3), Err("bad"), Ok(4)]).await?;
+                Ok(last)
+            });
+
+        assert_eq!(co.try_resume(), TryState::Yield(1));
+        assert_eq!(co.try_resume(), TryState::Yield(2));
+        assert_eq!(co.try_resume(), TryState::Yield(3));
+        assert_eq!(co.try_resume(), TryState::Error("bad"));
+    }
+
+    #[test]
+    fn test_try_collect() {
+        assert_eq!(
+            countdown(2).try_collect::<Vec<_>>(),
+            Ok((vec![2, 1, 0], "liftoff"))
+        );
+        assert_eq!(
+            countdown(14).try_collect::<Vec<_>>(),
+            Err("unlucky 13".to_string())
+        );
+    }
+}
@@ -0,0 +1,119 @@
+// Conversions between generators of this crate and `genawaiter`, enabled by the `genawaiter`
+// feature
+
+use crate::{Generator, Resumable, State};
+use genawaiter::{GeneratorState, rc::Gen};
+use std::{future::Future, pin::Pin};
+
+/// A `genawaiter` generator created from a [`Generator`] by [`Generator::into_genawaiter`].
+pub type GenawaiterGen<'a, Y, T> = Gen<Y, (), Pin<Box<dyn Future<Output = T> + 'a>>>;
+
+impl<'a, Y, T> Generator<'a, Y, T>
+where
+    Y: 'a,
+    T: 'a,
+{
+    /// Wraps a `genawaiter` generator into a [`Generator`].
+    #[track_caller]
+    pub fn from_genawaiter<F>(mut generator: Gen<Y, (), F>) -> Self
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Self::new(|handle, ()| async move {
+            loop {
+                match generator.resume() {
+                    GeneratorState::Yielded(value) => handle.yield_(value).await,
+                    GeneratorState::Complete(value) => break value,
+                }
+            }
+        })
+    }
+
+    /// Converts the generator into a `genawaiter` generator.
+    pub fn into_genawaiter(mut self) -> GenawaiterGen<'a, Y, T> {
+        Gen::new(|co| -> Pin<Box<dyn Future<Output = T> + 'a>> {
+            Box::pin(async move {
+                loop {
+                    match self.resume() {
+                        State::Yield(value) => co.yield_(value).await,
+                        State::Complete(value) => break value,
+                    }
+                }
+            })
+        })
+    }
+}
+
+impl<'a, Y, T, F> From<Gen<Y, (), F>> for Generator<'a, Y, T>
+where
+    Y: 'a,
+    T: 'a,
+    F: Future<Output = T> + 'a,
+{
+    #[track_caller]
+    fn from(generator: Gen<Y, (), F>) -> Self {
+        Self::from_genawaiter(generator)
+    }
+}
+
+/// Allows driving `genawaiter` generators through the same interface as coroutines of this crate.
+///
+/// Note that `genawaiter` drops the value of the first resume.
+impl<Y, R, F> Resumable<R> for Gen<Y, R, F>
+where
+    F: Future,
+{
+    type Yield = Y;
+    type Complete = F::Output;
+
+    fn resume_with(&mut self, resume: R) -> State<Y, F::Output> {
+        match Gen::resume_with(self, resume) {
+            GeneratorState::Yielded(value) => State::Yield(value),
+            GeneratorState::Complete(value) => State::Complete(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Generator, Resumable, State};
+    use genawaiter::{GeneratorState, rc::Gen};
+
+    #[test]
+    fn test_from_genawaiter() {
+        let mut generator = Generator::from(Gen::new(|co| async move {
+            co.yield_(1).await;
+            co.yield_(2).await;
+            "Bye"
+        }));
+
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(2));
+        assert_eq!(generator.resume(), State::Complete("Bye"));
+    }
+
+    #[test]
+    fn test_into_genawaiter() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            "Bye"
+        })
+        .into_genawaiter();
+
+        assert_eq!(generator.resume(), GeneratorState::Yielded(1));
+        assert_eq!(generator.resume(), GeneratorState::Complete("Bye"));
+    }
+
+    #[test]
+    fn test_resumable() {
+        let mut generators: Vec<Box<dyn Resumable<(), Yield = i32, Complete = ()>>> = vec![
+            Generator::new(|handle, ()| async move { handle.yield_(1).await }).boxed(),
+            Gen::new(|co| async move { co.yield_(2).await }).boxed(),
+        ];
+
+        for (generator, expected) in generators.iter_mut().zip([1, 2]) {
+            assert_eq!(generator.resume_with(()), State::Yield(expected));
+            assert_eq!(generator.resume_with(()), State::Complete(()));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// The implicit yield handle, see `CoroutineBuilder::implicit_handle`
+
+use crate::YieldHandle;
+use std::{any::Any, cell::RefCell, rc::Rc};
+
+thread_local! {
+    // The implicit handle of the coroutine whose body is being polled on this thread
+    static IMPLICIT: RefCell<Option<Rc<dyn Any>>> = const { RefCell::new(None) };
+}
+
+// Makes `handle` the implicit handle while `f` runs, also if `f` panics
+pub(crate) fn scope<O>(handle: Option<&Rc<dyn Any>>, f: impl FnOnce() -> O) -> O {
+    struct Restore(Option<Rc<dyn Any>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            IMPLICIT.set(self.0.take());
+        }
+    }
+
+    let _restore = Restore(IMPLICIT.replace(handle.cloned()));
+    f()
+}
+
+/// Yields a value from the coroutine that is being resumed and receives back the resume value,
+/// without access to its [`YieldHandle`].
+///
+/// This behaves like [`YieldHandle::yield_`], so helper functions called from the body do not need
+/// the handle as a parameter.
+///
+/// ```
+/// use async_coroutine::{Generator, State, yield_value};
+///
+/// async fn countdown(from: u32) {
+///     for value in (1..=from).rev() {
+///         yield_value::<u32, ()>(value).await;
+///     }
+/// }
+///
+/// let mut generator = Generator::<u32, ()>::builder()
+///     .implicit_handle()
+///     .build(|_handle, ()| countdown(2));
+///
+/// assert_eq!(generator.resume(), State::Yield(2));
+/// assert_eq!(generator.resume(), State::Yield(1));
+/// assert_eq!(generator.resume(), State::Complete(()));
+/// ```
+///
+/// # Panics
+///
+/// Panics if it is not called from the body of a coroutine that was created with
+/// [`CoroutineBuilder::implicit_handle`](crate::CoroutineBuilder::implicit_handle), or if the
+/// types do not match the yield and resume types of that coroutine.
+pub async fn yield_value<Y, R>(value: Y) -> R
+where
+    Y: 'static,
+    R: 'static,
+{
+    let handle = IMPLICIT.with_borrow(|handle| {
+        let handle = handle.as_ref().unwrap_or_else(|| {
+            panic!("`yield_value` was called outside of a coroutine with an implicit handle")
+        });
+        match handle.downcast_ref::<YieldHandle<Y, R>>() {
+            Some(handle) => handle.clone_(),
+            None => panic!(
+                "`yield_value` was called with the wrong types, the coroutine does not yield `{}` \
+                 or is not resumed with `{}`",
+                std::any::type_name::<Y>(),
+                std::any::type_name::<R>(),
+            ),
+        }
+    });
+    handle.yield_(value).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::yield_value;
+    use crate::{Coroutine, Generator, State};
+
+    async fn add(value: i32) -> i32 {
+        value + yield_value::<i32, i32>(value).await
+    }
+
+    #[test]
+    fn test_yield_value() {
+        let mut co = Coroutine::builder()
+            .implicit_handle()
+            .build(|_handle, value| async move {
+                let value = add(value).await;
+                add(value).await
+            });
+
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert_eq!(co.resume_with(2), State::Yield(3));
+        assert_eq!(co.resume_with(3), State::Complete(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "`yield_value` was called outside of a coroutine with an implicit")]
+    fn test_yield_value_without_implicit_handle() {
+        let mut outer =
+            Generator::<i32, ()>::builder()
+                .implicit_handle()
+                .build(|_handle, ()| async move {
+                    let mut inner = Generator::<i32, ()>::new(|_handle, ()| async move {
+                        yield_value::<i32, ()>(1).await;
+                    });
+                    inner.resume(); // This panics
+                });
+        outer.resume();
+    }
+
+    #[test]
+    #[should_panic(expected = "`yield_value` was called with the wrong types")]
+    fn test_yield_value_wrong_types() {
+        let mut generator =
+            Generator::<i32, ()>::builder()
+                .implicit_handle()
+                .build(|_handle, ()| async move {
+                    yield_value::<u8, ()>(1).await;
+                });
+        generator.resume();
+    }
+}
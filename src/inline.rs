@@ -0,0 +1,232 @@
+// A coroutine that stores small bodies inline instead of behind a `Box<dyn Future>`
+
+use crate::{Coroutine, CoroutineBuilder, ExecutorState, State, YieldHandle, executor::Body};
+use std::{
+    fmt,
+    future::Future,
+    marker::{PhantomData, PhantomPinned},
+    mem::{self, MaybeUninit},
+    ops::Deref,
+    pin::Pin,
+    ptr::NonNull,
+    task::{Context, Poll},
+};
+
+/// A [`Coroutine`] that stores its body inline if it is small enough, instead of boxing it.
+///
+/// Bodies of up to `N` bytes with an alignment of at most 16 are stored inline, `N` defaults to
+/// 128 bytes. A body that only holds the handle and a few integers across its yields takes about
+/// 80 bytes. Larger bodies are boxed like the body of a [`Coroutine`], see
+/// [`InlineCoroutine::is_inline`].
+///
+/// An inline body can not be moved, so the coroutine has to be pinned to be resumed, e.g. with
+/// [`pin!`](std::pin::pin). The [`Coroutine`] can be inspected through [`Deref`], but it can not
+/// be taken out.
+///
+/// ```
+/// use async_coroutine::{InlineCoroutine, State};
+/// use std::pin::pin;
+///
+/// let mut countdown = pin!(InlineCoroutine::new(|handle, start: u32| async move {
+///     for value in (1..=start).rev() {
+///         handle.yield_(value).await;
+///     }
+/// }));
+///
+/// assert!(countdown.is_inline());
+/// assert_eq!(countdown.as_mut().resume_with(2), State::Yield(2));
+/// assert_eq!(countdown.as_mut().resume_with(0), State::Yield(1));
+/// assert_eq!(countdown.as_mut().resume_with(0), State::Complete(()));
+/// ```
+pub struct InlineCoroutine<'a, Y, T, R, C, F, const N: usize = 128> {
+    coroutine: Coroutine<'a, Y, T, R>,
+    init: Option<C>,
+    // Dropped after the coroutine, which drops the body stored in it
+    buffer: Buffer<N>,
+    _pinned: PhantomPinned,
+    _body: PhantomData<fn() -> F>,
+}
+
+#[repr(align(16))]
+struct Buffer<const N: usize>([MaybeUninit<u8>; N]);
+
+impl<'a, Y, T, R, C, F> InlineCoroutine<'a, Y, T, R, C, F>
+where
+    C: FnOnce(YieldHandle<Y, R>, R) -> F + 'a,
+    F: Future<Output = T> + 'a,
+{
+    /// Creates a new coroutine with room for 128 bytes from a function that takes the
+    /// [`YieldHandle`] and the initial value, see [`Coroutine::new`].
+    ///
+    /// Use [`CoroutineBuilder::build_inline`] to choose another size.
+    #[track_caller]
+    pub fn new(f: C) -> Self {
+        CoroutineBuilder::new().build_inline(f)
+    }
+}
+
+impl<'a, Y, T, R, C, F, const N: usize> InlineCoroutine<'a, Y, T, R, C, F, N>
+where
+    C: FnOnce(YieldHandle<Y, R>, R) -> F + 'a,
+    F: Future<Output = T> + 'a,
+{
+    /// Returns `true` if the body is small enough to be stored inline.
+    pub const fn is_inline(&self) -> bool {
+        mem::size_of::<F>() <= N && mem::align_of::<F>() <= mem::align_of::<Buffer<N>>()
+    }
+
+    /// Resumes the coroutine with a value of type `R`, see [`Coroutine::resume_with`].
+    #[track_caller]
+    pub fn resume_with(self: Pin<&mut Self>, resume: R) -> State<Y, T> {
+        // SAFETY: Neither the buffer nor the coroutine pointing into it are moved
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Some(init) = this.init.take() {
+            let inline = this.is_inline();
+            let buffer = NonNull::from(&mut this.buffer).cast::<F>();
+            this.coroutine.executor.init = Some(Box::new(move |handle, initial_value| {
+                let future = init(handle, initial_value);
+                if inline {
+                    // SAFETY: The buffer is pinned and large and aligned enough for `F`
+                    unsafe {
+                        buffer.write(future);
+                        Body::Inline(InlineBody(buffer))
+                    }
+                } else {
+                    Body::Boxed(Box::pin(future))
+                }
+            }));
+        }
+        this.coroutine.resume_with(resume)
+    }
+}
+
+impl<'a, Y, T, C, F, const N: usize> InlineCoroutine<'a, Y, T, (), C, F, N>
+where
+    C: FnOnce(YieldHandle<Y, ()>, ()) -> F + 'a,
+    F: Future<Output = T> + 'a,
+{
+    /// Resumes the underlying generator.
+    #[track_caller]
+    pub fn resume(self: Pin<&mut Self>) -> State<Y, T> {
+        self.resume_with(())
+    }
+}
+
+impl<'a, Y, T, R, C, F, const N: usize> Deref for InlineCoroutine<'a, Y, T, R, C, F, N> {
+    type Target = Coroutine<'a, Y, T, R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.coroutine
+    }
+}
+
+impl<Y, T, R, C, F, const N: usize> fmt::Debug for InlineCoroutine<'_, Y, T, R, C, F, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InlineCoroutine")
+            .field("coroutine", &self.coroutine)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, Y, T, R> CoroutineBuilder<'a, Y, T, R> {
+    /// Creates the coroutine like [`CoroutineBuilder::build`], but stores the body inline if it
+    /// fits into `N` bytes, see [`InlineCoroutine`].
+    #[track_caller]
+    pub fn build_inline<C, F, const N: usize>(self, f: C) -> InlineCoroutine<'a, Y, T, R, C, F, N>
+    where
+        C: FnOnce(YieldHandle<Y, R>, R) -> F + 'a,
+        F: Future<Output = T> + 'a,
+    {
+        InlineCoroutine {
+            // The body is created by the first resume, once the buffer is pinned
+            coroutine: self.build_executor(ExecutorState {
+                init: None,
+                executor: None,
+                factory: None,
+            }),
+            init: Some(f),
+            buffer: Buffer([MaybeUninit::uninit(); N]),
+            _pinned: PhantomPinned,
+            _body: PhantomData,
+        }
+    }
+}
+
+// A body stored in the buffer of an `InlineCoroutine`
+pub(crate) struct InlineBody<'a, T>(NonNull<dyn Future<Output = T> + 'a>);
+
+impl<T> InlineBody<'_, T> {
+    pub(crate) fn poll(&mut self, context: &mut Context<'_>) -> Poll<T> {
+        // SAFETY: The buffer is pinned
+        unsafe { Pin::new_unchecked(self.0.as_mut()) }.poll(context)
+    }
+}
+
+impl<T> Drop for InlineBody<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: The body is dropped before the buffer
+        unsafe { self.0.drop_in_place() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineCoroutine;
+    use crate::{Coroutine, State};
+    use std::{pin::pin, rc::Rc};
+
+    #[test]
+    fn test_inline_coroutine() {
+        let alive = Rc::new(());
+        let mut co = pin!(InlineCoroutine::new({
+            let alive = Rc::clone(&alive);
+            |handle, mut sum: u32| async move {
+                let _alive = alive;
+                while sum < 10 {
+                    sum += handle.yield_(sum).await;
+                }
+                sum
+            }
+        }));
+
+        assert!(co.is_inline());
+        assert_eq!(co.as_mut().resume_with(1), State::Yield(1));
+        assert_eq!(co.as_mut().resume_with(4), State::Yield(5));
+        assert_eq!(co.stats().resumes, 2);
+        assert_eq!(co.as_mut().resume_with(5), State::Complete(10));
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_inline_coroutine_fallback() {
+        let mut co = pin!(Coroutine::builder().name("large").build_inline::<_, _, 16>(
+            |handle, ()| async move {
+                let buffer = [1u8; 64];
+                handle.yield_(buffer[0]).await;
+                buffer.iter().map(|&value| u32::from(value)).sum::<u32>()
+            }
+        ));
+
+        assert!(!co.is_inline());
+        assert_eq!(co.name(), Some("large"));
+        assert_eq!(co.as_mut().resume(), State::Yield(1));
+        assert_eq!(co.as_mut().resume(), State::Complete(64));
+    }
+
+    #[test]
+    fn test_inline_coroutine_drop_suspended() {
+        let alive = Rc::new(());
+        let mut co = Box::pin(InlineCoroutine::new({
+            let alive = Rc::clone(&alive);
+            |handle, ()| async move {
+                let _alive = alive;
+                handle.yield_(()).await;
+            }
+        }));
+
+        assert_eq!(co.as_mut().resume(), State::Yield(()));
+        assert_eq!(Rc::strong_count(&alive), 2);
+        drop(co);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+}
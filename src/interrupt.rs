@@ -0,0 +1,216 @@
+//! Feeding coroutines from interrupt handlers.
+//!
+//! A coroutine is not `Sync`, so it has to be driven by the main loop. An [`Inbox`] is the only
+//! state shared with interrupt handlers: it holds at most one pending resume value and a
+//! cancellation flag, both protected by [`critical_section::with`]. A [`Guarded`] coroutine takes
+//! the pending value in a critical section and resumes the coroutine outside of it, so interrupts
+//! are only masked for the few instructions it takes to move the value.
+//!
+//! The allowed patterns are:
+//!
+//! - Interrupt handlers call [`Inbox::send`], [`Inbox::cancel`] and [`Inbox::is_cancelled`].
+//! - The main loop owns the [`Guarded`] coroutine and calls [`Guarded::poll`].
+//! - The body may check [`Inbox::is_cancelled`] to stop early, but must not call
+//!   [`Inbox::take`], which would steal the values meant for its own resumes.
+//!
+//! Resuming a coroutine from an interrupt handler is not possible, the coroutine can not even be
+//! moved there.
+//!
+//! ```
+//! use async_coroutine::{Cancelled, Coroutine, State, interrupt::{Guarded, Inbox}};
+//!
+//! static BUTTON: Inbox<u32> = Inbox::new();
+//!
+//! let mut presses = Guarded::new(
+//!     Coroutine::new(|handle, mut pin: u32| async move {
+//!         loop {
+//!             pin = handle.yield_(pin * 10).await;
+//!         }
+//!     }),
+//!     &BUTTON,
+//! );
+//!
+//! // The main loop polls, interrupt handlers send
+//! assert_eq!(presses.poll(), Ok(None));
+//! BUTTON.send(1).unwrap();
+//! assert_eq!(BUTTON.send(2), Err(2));
+//! assert_eq!(presses.poll(), Ok(Some(State::Yield(10))));
+//!
+//! BUTTON.cancel();
+//! assert_eq!(presses.poll(), Err(Cancelled));
+//! ```
+
+use crate::{Cancelled, Coroutine, State};
+use critical_section::Mutex;
+use std::{cell::RefCell, fmt};
+
+/// A resume value and a cancellation flag that can be shared with interrupt handlers.
+///
+/// The inbox is usually stored in a `static`, it is `Sync` if the resume value is `Send`.
+pub struct Inbox<R> {
+    state: Mutex<RefCell<InboxState<R>>>,
+}
+
+struct InboxState<R> {
+    pending: Option<R>,
+    cancelled: bool,
+}
+
+impl<R> Inbox<R> {
+    /// Creates an empty inbox.
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(InboxState {
+                pending: None,
+                cancelled: false,
+            })),
+        }
+    }
+
+    /// Stores the value to resume the coroutine with next.
+    ///
+    /// Returns the value if the previous one has not been taken yet or the inbox was cancelled.
+    pub fn send(&self, value: R) -> Result<(), R> {
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            if state.pending.is_some() || state.cancelled {
+                return Err(value);
+            }
+            state.pending = Some(value);
+            Ok(())
+        })
+    }
+
+    /// Takes the pending value, if there is one.
+    pub fn take(&self) -> Option<R> {
+        critical_section::with(|cs| self.state.borrow_ref_mut(cs).pending.take())
+    }
+
+    /// Cancels the coroutine, it is not resumed anymore and a pending value is dropped.
+    pub fn cancel(&self) {
+        // The value is dropped outside of the critical section
+        let pending = critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            state.cancelled = true;
+            state.pending.take()
+        });
+        drop(pending);
+    }
+
+    /// Returns `true` if the inbox was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        critical_section::with(|cs| self.state.borrow_ref(cs).cancelled)
+    }
+}
+
+impl<R> Default for Inbox<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> fmt::Debug for Inbox<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (pending, cancelled) = critical_section::with(|cs| {
+            let state = self.state.borrow_ref(cs);
+            (state.pending.is_some(), state.cancelled)
+        });
+        f.debug_struct("Inbox")
+            .field("pending", &pending)
+            .field("cancelled", &cancelled)
+            .finish()
+    }
+}
+
+/// A coroutine that is resumed with the values sent to an [`Inbox`].
+pub struct Guarded<'a, Y, T, R> {
+    coroutine: Coroutine<'a, Y, T, R>,
+    inbox: &'a Inbox<R>,
+}
+
+impl<'a, Y, T, R> Guarded<'a, Y, T, R> {
+    /// Wraps a coroutine that is resumed with the values sent to `inbox`. The first value becomes
+    /// its initial value.
+    pub fn new(coroutine: Coroutine<'a, Y, T, R>, inbox: &'a Inbox<R>) -> Self {
+        Self { coroutine, inbox }
+    }
+
+    /// Resumes the coroutine if a value is pending.
+    ///
+    /// Returns `Ok(None)` if no value is pending and [`Cancelled`] if the inbox was cancelled.
+    #[track_caller]
+    pub fn poll(&mut self) -> Result<Option<State<Y, T>>, Cancelled> {
+        if self.inbox.is_cancelled() {
+            return Err(Cancelled);
+        }
+        Ok(self
+            .inbox
+            .take()
+            .map(|resume| self.coroutine.resume_with(resume)))
+    }
+
+    /// Returns the inbox the coroutine is resumed from.
+    pub fn inbox(&self) -> &'a Inbox<R> {
+        self.inbox
+    }
+
+    /// Returns the wrapped coroutine.
+    pub fn into_inner(self) -> Coroutine<'a, Y, T, R> {
+        self.coroutine
+    }
+}
+
+impl<Y, T, R> fmt::Debug for Guarded<'_, Y, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Guarded")
+            .field("coroutine", &self.coroutine)
+            .field("inbox", &self.inbox)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Guarded, Inbox};
+    use crate::{Cancelled, Coroutine, State};
+    use std::thread;
+
+    #[test]
+    fn test_inbox_from_other_context() {
+        static INBOX: Inbox<u32> = Inbox::new();
+
+        let mut sum = Guarded::new(
+            Coroutine::new(|handle, mut value: u32| async move {
+                let mut sum = 0;
+                while !INBOX.is_cancelled() {
+                    sum += value;
+                    value = handle.yield_(sum).await;
+                }
+                sum
+            }),
+            &INBOX,
+        );
+
+        // Another thread stands in for the interrupt handler
+        let mut sums = Vec::new();
+        let interrupt = thread::spawn(|| {
+            for value in 1..=3 {
+                while INBOX.send(value).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+        while sums.len() < 3 {
+            if let Some(state) = sum.poll().unwrap() {
+                sums.push(state.unwrap_yield());
+            }
+        }
+        interrupt.join().unwrap();
+        assert_eq!(sums, [1, 3, 6]);
+
+        INBOX.cancel();
+        assert_eq!(INBOX.send(4), Err(4));
+        assert_eq!(sum.poll(), Err(Cancelled));
+        assert_eq!(sum.into_inner().resume_with(0), State::Complete(6));
+    }
+}
@@ -0,0 +1,413 @@
+//! Adapters that expose coroutines producing or consuming byte chunks as I/O objects.
+//!
+//! A [`Reader`] reads the chunks yielded by a generator, a [`Writer`] resumes a consumer with the
+//! written chunks. Besides [`Read`](io::Read), [`BufRead`](io::BufRead) and [`Write`](io::Write),
+//! they implement the `AsyncRead` and `AsyncWrite` traits of the enabled `tokio` and `futures`
+//! features, which never return `Pending` since coroutines are resumed synchronously.
+//!
+//! The [`protocol`] module goes the other way and lets coroutines perform network I/O by yielding
+//! commands to a driver.
+
+pub mod protocol;
+
+use crate::{Consumer, Generator, State};
+use std::io;
+
+impl<'a, Y, T> Generator<'a, Y, T>
+where
+    Y: Chunk,
+{
+    /// Creates a [`Reader`] that reads the chunks yielded by the generator.
+    pub fn into_reader(self) -> Reader<'a, Y, T> {
+        Reader {
+            generator: self,
+            chunk: Vec::new(),
+            position: 0,
+            complete: None,
+            done: false,
+        }
+    }
+}
+
+impl<'a, T> Consumer<'a, T, Vec<u8>> {
+    /// Creates a [`Writer`] that resumes the consumer with every written chunk.
+    pub fn into_writer(self) -> Writer<'a, T> {
+        Writer {
+            consumer: self,
+            complete: None,
+        }
+    }
+}
+
+/// A chunk of bytes that can be yielded by a generator read through a [`Reader`].
+pub trait Chunk {
+    /// Converts the chunk into its bytes, or the error that is returned by the read.
+    fn into_bytes(self) -> io::Result<Vec<u8>>;
+}
+
+impl Chunk for Vec<u8> {
+    fn into_bytes(self) -> io::Result<Vec<u8>> {
+        Ok(self)
+    }
+}
+
+impl Chunk for io::Result<Vec<u8>> {
+    fn into_bytes(self) -> io::Result<Vec<u8>> {
+        self
+    }
+}
+
+/// Reads the chunks yielded by a generator, created by [`Generator::into_reader`].
+///
+/// Chunks that are only read partially are carried over to the next read. The reader reaches the
+/// end once the generator completes.
+pub struct Reader<'a, Y, T> {
+    generator: Generator<'a, Y, T>,
+    chunk: Vec<u8>,
+    position: usize,
+    complete: Option<T>,
+    done: bool,
+}
+
+impl<Y, T> Reader<'_, Y, T>
+where
+    Y: Chunk,
+{
+    /// Takes the completion value of the generator once the reader has reached the end.
+    pub fn take_complete(&mut self) -> Option<T> {
+        self.complete.take()
+    }
+
+    // Resumes the generator until a non-empty chunk is available, returns an empty slice at the end
+    fn fill(&mut self) -> io::Result<&[u8]> {
+        while self.position == self.chunk.len() && !self.done {
+            match self.generator.resume() {
+                State::Yield(chunk) => {
+                    self.chunk = chunk.into_bytes()?;
+                    self.position = 0;
+                }
+                State::Complete(value) => {
+                    self.complete = Some(value);
+                    self.done = true;
+                }
+            }
+        }
+        Ok(&self.chunk[self.position..])
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let chunk = self.fill()?;
+        let len = chunk.len().min(buf.len());
+        buf[..len].copy_from_slice(&chunk[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl<Y, T> io::Read for Reader<'_, Y, T>
+where
+    Y: Chunk,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_into(buf)
+    }
+}
+
+/// Hands out the rest of the current chunk without copying it.
+impl<Y, T> io::BufRead for Reader<'_, Y, T>
+where
+    Y: Chunk,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.fill()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.chunk.len());
+    }
+}
+
+// The async reads unpin the reader with `get_mut`, the completion value it keeps is never pinned
+impl<Y, T> Unpin for Reader<'_, Y, T> {}
+
+/// Resumes a consumer with the written chunks, created by [`Consumer::into_writer`].
+///
+/// The first write starts the consumer. Once it completes, further writes fail with
+/// [`io::ErrorKind::BrokenPipe`].
+pub struct Writer<'a, T> {
+    consumer: Consumer<'a, T, Vec<u8>>,
+    complete: Option<T>,
+}
+
+impl<T> Writer<'_, T> {
+    /// Takes the completion value of the consumer once it has completed.
+    pub fn take_complete(&mut self) -> Option<T> {
+        self.complete.take()
+    }
+
+    fn write_chunk(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.consumer.completed {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "consumer has already completed",
+            ));
+        }
+        if let State::Complete(value) = self.consumer.resume_with(buf.to_vec()) {
+            self.complete = Some(value);
+        }
+        Ok(buf.len())
+    }
+}
+
+impl<T> io::Write for Writer<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_chunk(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The async writes unpin the writer to resume the consumer, so a completion value that is not
+// `Unpin` must not make the writer `!Unpin`
+impl<T> Unpin for Writer<'_, T> {}
+
+#[cfg(feature = "tokio")]
+mod tokio_impls {
+    use super::{Chunk, Reader, Writer};
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    impl<Y, T> AsyncRead for Reader<'_, Y, T>
+    where
+        Y: Chunk,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let len = self.get_mut().read_into(buf.initialize_unfilled())?;
+            buf.advance(len);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl<T> AsyncWrite for Writer<'_, T> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(self.get_mut().write_chunk(buf))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+mod futures_impls {
+    use super::{Chunk, Reader, Writer};
+    use futures_io::{AsyncRead, AsyncWrite};
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    impl<Y, T> AsyncRead for Reader<'_, Y, T>
+    where
+        Y: Chunk,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(self.get_mut().read_into(buf))
+        }
+    }
+
+    impl<T> AsyncWrite for Writer<'_, T> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(self.get_mut().write_chunk(buf))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Consumer, Generator};
+    use std::{
+        cell::RefCell,
+        io::{self, BufRead, Read, Write},
+        rc::Rc,
+    };
+    #[cfg(any(feature = "tokio", feature = "futures"))]
+    use std::{
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+
+    fn chunks() -> Generator<'static, Vec<u8>, &'static str> {
+        Generator::new(|handle, ()| async move {
+            handle.yield_(b"Hello".to_vec()).await;
+            handle.yield_(Vec::new()).await;
+            handle.yield_(b", World".to_vec()).await;
+            "Bye"
+        })
+    }
+
+    fn collector(received: &Rc<RefCell<Vec<u8>>>) -> Consumer<'static, usize, Vec<u8>> {
+        let received = Rc::clone(received);
+        Consumer::new(move |handle, mut chunk: Vec<u8>| async move {
+            let mut chunks = 0;
+            while !chunk.is_empty() {
+                received.borrow_mut().extend(&chunk);
+                chunks += 1;
+                chunk = handle.recv().await;
+            }
+            chunks
+        })
+    }
+
+    #[test]
+    fn test_read() {
+        let mut reader = chunks().into_reader();
+        let mut buf = [0; 3];
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf, b"Hel");
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "lo, World");
+        assert_eq!(reader.take_complete(), Some("Bye"));
+    }
+
+    #[test]
+    fn test_buf_read() {
+        let mut reader = Generator::new(|handle, ()| async move {
+            handle.yield_(b"first\nsec".to_vec()).await;
+            handle.yield_(b"ond\nthird".to_vec()).await;
+        })
+        .into_reader();
+
+        assert_eq!(reader.fill_buf().unwrap(), b"first\nsec");
+        reader.consume(6);
+        assert_eq!(reader.fill_buf().unwrap(), b"sec");
+        let lines = reader.lines().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(lines, ["second", "third"]);
+    }
+
+    #[test]
+    fn test_read_error() {
+        let mut reader = Generator::new(|handle, ()| async move {
+            handle.yield_(Ok(b"ab".to_vec())).await;
+            handle.yield_(Err(io::Error::other("broken"))).await;
+        })
+        .into_reader();
+
+        let mut data = Vec::new();
+        let err = reader.read_to_end(&mut data).unwrap_err();
+        assert_eq!(err.to_string(), "broken");
+        assert_eq!(data, b"ab");
+    }
+
+    #[test]
+    fn test_write() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut writer = collector(&received).into_writer();
+
+        writer.write_all(b"ab").unwrap();
+        writer.write_all(b"c").unwrap();
+        assert_eq!(*received.borrow(), b"abc");
+        assert_eq!(writer.write(b"").unwrap(), 0);
+        assert_eq!(writer.take_complete(), Some(2));
+        assert_eq!(
+            writer.write(b"x").unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio() {
+        use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+        let mut context = Context::from_waker(Waker::noop());
+
+        let mut reader = chunks().into_reader();
+        let mut data = Vec::<u8>::new();
+        loop {
+            let mut buf = [0; 4];
+            let mut buf = ReadBuf::new(&mut buf);
+            let poll = Pin::new(&mut reader).poll_read(&mut context, &mut buf);
+            assert!(matches!(poll, Poll::Ready(Ok(()))));
+            if buf.filled().is_empty() {
+                break;
+            }
+            data.extend(buf.filled());
+        }
+        assert_eq!(data, b"Hello, World");
+        assert_eq!(reader.take_complete(), Some("Bye"));
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut writer = collector(&received).into_writer();
+        for chunk in [&b"ab"[..], b"c", b""] {
+            let poll = Pin::new(&mut writer).poll_write(&mut context, chunk);
+            assert!(matches!(poll, Poll::Ready(Ok(len)) if len == chunk.len()));
+        }
+        let poll = Pin::new(&mut writer).poll_write(&mut context, b"d");
+        assert!(matches!(poll, Poll::Ready(Err(_))));
+        assert_eq!(*received.borrow(), b"abc");
+        assert_eq!(writer.take_complete(), Some(2));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_futures() {
+        use futures_io::{AsyncRead, AsyncWrite};
+
+        let mut context = Context::from_waker(Waker::noop());
+
+        let mut reader = chunks().into_reader();
+        let mut data = Vec::<u8>::new();
+        let mut buf = [0; 4];
+        while let Poll::Ready(Ok(len @ 1..)) =
+            Pin::new(&mut reader).poll_read(&mut context, &mut buf)
+        {
+            data.extend(&buf[..len]);
+        }
+        assert_eq!(data, b"Hello, World");
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut writer = collector(&received).into_writer();
+        let poll = Pin::new(&mut writer).poll_write(&mut context, b"abc");
+        assert!(matches!(poll, Poll::Ready(Ok(3))));
+        assert_eq!(*received.borrow(), b"abc");
+    }
+}
@@ -0,0 +1,603 @@
+//! Network protocols written as coroutines that are independent of the I/O implementation.
+//!
+//! A [`Protocol`] yields a [`Command`] whenever it needs to perform I/O and is resumed with the
+//! [`Response`] or the error. The same protocol can be run with blocking `std::net` I/O by
+//! [`run_blocking`] or, with the `tokio` feature, asynchronously by `run_tokio`, and tested by
+//! resuming it with scripted responses. HTTP requests are only performed by `run_tokio` with the
+//! `reqwest` feature, the other drivers answer them with an [`io::ErrorKind::Unsupported`] error.
+//!
+//! The drivers can retry failed commands according to a [`RetryPolicy`], so the protocol only sees
+//! the final outcome.
+
+use crate::Coroutine;
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    io::{self, Read, Write},
+    net::TcpStream,
+    thread,
+    time::Duration,
+};
+
+/// A coroutine that implements a network protocol.
+///
+/// It is started with [`Response::Start`].
+pub type Protocol<T> = Coroutine<'static, Command, T, io::Result<Response>>;
+
+/// An I/O operation requested by a [`Protocol`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Opens a TCP connection to the given address, replacing the current one.
+    Connect(String),
+    /// Writes all the bytes to the connection.
+    Send(Vec<u8>),
+    /// Reads at most `max` bytes from the connection.
+    Recv {
+        /// The maximum number of bytes to read.
+        max: usize,
+    },
+    /// Waits for the given duration.
+    Sleep(Duration),
+    /// Performs an HTTP request, independent of the current connection.
+    Http(HttpRequest),
+}
+
+/// The result of a [`Command`] a [`Protocol`] is resumed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// The initial value of the protocol.
+    Start,
+    /// The connection was opened.
+    Connected,
+    /// All bytes were written.
+    Sent,
+    /// The bytes that were read, empty if the connection was closed.
+    Received(Vec<u8>),
+    /// The duration has passed.
+    Slept,
+    /// The response to the HTTP request.
+    Http(HttpResponse),
+}
+
+/// An HTTP request performed by [`Command::Http`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// The method, e.g. `GET`.
+    pub method: String,
+    /// The URL.
+    pub url: String,
+    /// The header names and values.
+    pub headers: Vec<(String, String)>,
+    /// The body.
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Creates a request without headers and body.
+    pub fn new(method: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            method: method.into(),
+            url: url.into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Creates a `GET` request.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url)
+    }
+
+    /// Creates a `POST` request with the given body.
+    pub fn post(url: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self::new("POST", url).body(body)
+    }
+
+    /// Adds a header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// The response to an [`HttpRequest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// The status code.
+    pub status: u16,
+    /// The header names and values.
+    pub headers: Vec<(String, String)>,
+    /// The body.
+    pub body: Vec<u8>,
+}
+
+/// Decides whether and when failed commands are retried.
+///
+/// The delay before the first retry is the initial backoff, every further retry multiplies it by
+/// the multiplier up to the maximum backoff. With jitter, every delay is scaled by a random factor
+/// in `1 - jitter..=1 + jitter`. By default, commands are retried at most 3 times with an
+/// initial backoff of 100ms, a multiplier of 2, a maximum backoff of 10s and no jitter, if the
+/// error is transient, i.e. a connection was refused, reset or aborted, or an operation timed out
+/// or was interrupted.
+///
+/// Note that retrying [`Command::Send`] may send a prefix of the bytes twice.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: usize,
+    backoff: Duration,
+    multiplier: f64,
+    max_backoff: Duration,
+    jitter: f64,
+    retry_if: fn(&io::Error) -> bool,
+}
+
+impl RetryPolicy {
+    /// Creates the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a policy that never retries.
+    pub fn never() -> Self {
+        Self::new().max_retries(0)
+    }
+
+    /// Sets the maximum number of retries per command.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the factor the delay grows by with every retry.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the maximum delay before a retry, not including jitter.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the fraction in `0..=1` by which delays are randomly scaled up or down.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the predicate that decides which errors are retried.
+    pub fn retry_if(mut self, retry_if: fn(&io::Error) -> bool) -> Self {
+        self.retry_if = retry_if;
+        self
+    }
+
+    /// Returns the delay before the retry with the given index, starting at 0.
+    pub fn delay(&self, retry: usize) -> Duration {
+        let exponent = i32::try_from(retry).unwrap_or(i32::MAX);
+        let backoff = self.backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+        let scale = if self.jitter > 0.0 {
+            // A random number in -1..=1
+            let random = RandomState::new().hash_one(retry) as f64 / u64::MAX as f64 * 2.0 - 1.0;
+            1.0 + self.jitter * random
+        } else {
+            1.0
+        };
+        Duration::from_secs_f64(backoff * scale)
+    }
+
+    /// Performs `op` until it succeeds, fails with an error that is not retried or the retries run
+    /// out, sleeping the current thread between the attempts.
+    pub fn retry<R>(&self, mut op: impl FnMut() -> io::Result<R>) -> io::Result<R> {
+        let mut retry = 0;
+        loop {
+            match op() {
+                Err(err) if self.retries(retry, &err) => {
+                    thread::sleep(self.delay(retry));
+                    retry += 1;
+                }
+                result => break result,
+            }
+        }
+    }
+
+    // Whether the failed attempt is retried after `retry` retries
+    fn retries(&self, retry: usize, err: &io::Error) -> bool {
+        retry < self.max_retries && (self.retry_if)(err)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.0,
+            retry_if: is_transient,
+        }
+    }
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+    )
+}
+
+/// Runs the protocol to completion, performing the commands with blocking `std::net` I/O.
+pub fn run_blocking<T>(protocol: Protocol<T>) -> T {
+    run_blocking_with_retry(protocol, &RetryPolicy::never())
+}
+
+/// Runs the protocol like [`run_blocking`], but retries failed commands according to `policy`.
+pub fn run_blocking_with_retry<T>(protocol: Protocol<T>, policy: &RetryPolicy) -> T {
+    let mut stream = None;
+    protocol.run(Ok(Response::Start), |command| {
+        policy.retry(|| execute_blocking(&mut stream, command.clone()))
+    })
+}
+
+fn execute_blocking(stream: &mut Option<TcpStream>, command: Command) -> io::Result<Response> {
+    match command {
+        Command::Connect(addr) => {
+            *stream = Some(TcpStream::connect(addr)?);
+            Ok(Response::Connected)
+        }
+        Command::Send(bytes) => {
+            connection(stream)?.write_all(&bytes)?;
+            Ok(Response::Sent)
+        }
+        Command::Recv { max } => {
+            let mut buf = vec![0; max];
+            let len = connection(stream)?.read(&mut buf)?;
+            buf.truncate(len);
+            Ok(Response::Received(buf))
+        }
+        Command::Sleep(duration) => {
+            thread::sleep(duration);
+            Ok(Response::Slept)
+        }
+        Command::Http(_) => Err(http_unsupported()),
+    }
+}
+
+/// Runs the protocol to completion, performing the commands with asynchronous tokio I/O.
+///
+/// With the `reqwest` feature, HTTP requests are performed by a `reqwest` client that is created
+/// on the first request.
+#[cfg(feature = "tokio")]
+pub async fn run_tokio<T>(protocol: Protocol<T>) -> T {
+    run_tokio_with_retry(protocol, &RetryPolicy::never()).await
+}
+
+/// Runs the protocol like `run_tokio`, but retries failed commands according to `policy`.
+#[cfg(feature = "tokio")]
+pub async fn run_tokio_with_retry<T>(mut protocol: Protocol<T>, policy: &RetryPolicy) -> T {
+    use crate::State;
+
+    let mut driver = TokioDriver::default();
+    let mut response = Ok(Response::Start);
+    loop {
+        match protocol.resume_with(response) {
+            State::Yield(command) => {
+                let mut retry = 0;
+                response = loop {
+                    match driver.execute(command.clone()).await {
+                        Err(err) if policy.retries(retry, &err) => {
+                            tokio::time::sleep(policy.delay(retry)).await;
+                            retry += 1;
+                        }
+                        result => break result,
+                    }
+                };
+            }
+            State::Complete(value) => break value,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[derive(Default)]
+struct TokioDriver {
+    stream: Option<tokio::net::TcpStream>,
+    #[cfg(feature = "reqwest")]
+    client: Option<reqwest::Client>,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioDriver {
+    async fn execute(&mut self, command: Command) -> io::Result<Response> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        match command {
+            Command::Connect(addr) => {
+                self.stream = Some(tokio::net::TcpStream::connect(addr).await?);
+                Ok(Response::Connected)
+            }
+            Command::Send(bytes) => {
+                connection(&mut self.stream)?.write_all(&bytes).await?;
+                Ok(Response::Sent)
+            }
+            Command::Recv { max } => {
+                let mut buf = vec![0; max];
+                let len = connection(&mut self.stream)?.read(&mut buf).await?;
+                buf.truncate(len);
+                Ok(Response::Received(buf))
+            }
+            Command::Sleep(duration) => {
+                tokio::time::sleep(duration).await;
+                Ok(Response::Slept)
+            }
+            #[cfg(feature = "reqwest")]
+            Command::Http(request) => {
+                let client = self.client.get_or_insert_with(reqwest::Client::new);
+                Ok(Response::Http(execute_reqwest(client, request).await?))
+            }
+            #[cfg(not(feature = "reqwest"))]
+            Command::Http(_) => Err(http_unsupported()),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+async fn execute_reqwest(
+    client: &reqwest::Client,
+    request: HttpRequest,
+) -> io::Result<HttpResponse> {
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut builder = client.request(method, request.url).body(request.body);
+    for (name, value) in request.headers {
+        builder = builder.header(name, value);
+    }
+
+    let response = builder.send().await.map_err(io::Error::other)?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.to_string(), value)
+        })
+        .collect();
+    let body = response.bytes().await.map_err(io::Error::other)?.to_vec();
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn http_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "HTTP requests are not supported by this driver",
+    )
+}
+
+fn connection<S>(stream: &mut Option<S>) -> io::Result<&mut S> {
+    stream
+        .as_mut()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "command requires a connection"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Command, HttpRequest, HttpResponse, Protocol, Response, RetryPolicy, run_blocking,
+        run_blocking_with_retry,
+    };
+    use crate::{Coroutine, State};
+    use std::{
+        io::{self, Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    // Sends a greeting and returns the reply
+    fn greet(addr: String) -> Protocol<io::Result<Vec<u8>>> {
+        Coroutine::new(|handle, _start| async move {
+            handle.yield_(Command::Connect(addr)).await?;
+            handle.yield_(Command::Send(b"ping".to_vec())).await?;
+            match handle.yield_(Command::Recv { max: 16 }).await? {
+                Response::Received(bytes) => Ok(bytes),
+                response => panic!("unexpected response {response:?}"),
+            }
+        })
+    }
+
+    fn echo_server() -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+        });
+        (addr, server)
+    }
+
+    #[test]
+    fn test_scripted() {
+        let mut protocol = greet("server".into());
+        assert_eq!(
+            protocol.resume_with(Ok(Response::Start)).as_yield(),
+            Some(&Command::Connect("server".into()))
+        );
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        let State::Complete(Err(err)) = protocol.resume_with(Err(refused)) else {
+            panic!("protocol did not fail");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn test_run_blocking() {
+        let (addr, server) = echo_server();
+        assert_eq!(run_blocking(greet(addr)).unwrap(), b"ping");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::new()
+            .backoff(Duration::from_millis(100))
+            .multiplier(2.0)
+            .max_backoff(Duration::from_millis(300));
+        let delays = (0..4).map(|retry| policy.delay(retry)).collect::<Vec<_>>();
+        assert_eq!(delays, [100, 200, 300, 300].map(Duration::from_millis));
+
+        let policy = policy.jitter(0.5);
+        for retry in 0..4 {
+            let delay = policy.delay(retry);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(450));
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        let policy = RetryPolicy::new().backoff(Duration::ZERO).max_retries(2);
+
+        let mut attempts = 0;
+        let result = policy.retry(|| {
+            attempts += 1;
+            match attempts {
+                1 => Err(io::ErrorKind::ConnectionReset.into()),
+                2 => Err(io::ErrorKind::TimedOut.into()),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result = policy.retry(|| {
+            attempts += 1;
+            Err::<(), _>(io::Error::from(io::ErrorKind::ConnectionRefused))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result = policy.retry(|| {
+            attempts += 1;
+            Err::<(), _>(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_run_blocking_with_retry() {
+        let (addr, server) = echo_server();
+        let policy = RetryPolicy::new()
+            .retry_if(|_| true)
+            .backoff(Duration::ZERO);
+        assert_eq!(
+            run_blocking_with_retry(greet(addr), &policy).unwrap(),
+            b"ping"
+        );
+        server.join().unwrap();
+    }
+
+    // Fetches the body of a resource if it exists
+    fn fetch(url: &str) -> Protocol<io::Result<Option<Vec<u8>>>> {
+        let request = HttpRequest::get(url).header("Accept", "text/plain");
+        Coroutine::new(|handle, _start| async move {
+            match handle.yield_(Command::Http(request)).await? {
+                Response::Http(response) if response.status == 404 => Ok(None),
+                Response::Http(response) => Ok(Some(response.body)),
+                response => panic!("unexpected response {response:?}"),
+            }
+        })
+    }
+
+    #[test]
+    fn test_http_scripted() {
+        let mut protocol = fetch("http://example.com");
+        let State::Yield(Command::Http(request)) = protocol.resume_with(Ok(Response::Start)) else {
+            panic!("protocol did not send a request");
+        };
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.headers, [("Accept".into(), "text/plain".into())]);
+
+        let response = HttpResponse {
+            status: 404,
+            ..HttpResponse::default()
+        };
+        let State::Complete(Ok(None)) = protocol.resume_with(Ok(Response::Http(response))) else {
+            panic!("protocol did not handle the missing resource");
+        };
+    }
+
+    #[test]
+    fn test_http_unsupported() {
+        let err = run_blocking(fetch("http://example.com")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn test_run_tokio_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/greeting", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let len = stream.read(&mut buf).unwrap();
+                request.extend(&buf[..len]);
+            }
+            assert!(request.starts_with(b"GET /greeting HTTP/1.1\r\n"));
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nHello",
+                )
+                .unwrap();
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let body = runtime.block_on(super::run_tokio(fetch(&url)));
+        assert_eq!(body.unwrap().unwrap(), b"Hello");
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_run_tokio() {
+        let (addr, server) = echo_server();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let reply = runtime.block_on(super::run_tokio(greet(addr)));
+        assert_eq!(reply.unwrap(), b"ping");
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+// Handing yielded values to the driver without moving them through `State`, see
+// `YieldHandle::yield_ref` and `Coroutine::resume_into`
+
+use crate::{Coroutine, Origin, State, YieldHandle};
+use std::ptr::NonNull;
+
+/// The outcome of [`Coroutine::resume_into`], which stores the yielded value in caller-provided
+/// storage instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Resumed<T> {
+    /// The coroutine yielded a value, which was written to the storage.
+    Yield,
+    /// The coroutine completed with a value.
+    Complete(T),
+}
+
+impl<T> Resumed<T> {
+    /// Returns `true` if the coroutine yielded.
+    pub fn is_yield(&self) -> bool {
+        matches!(self, Self::Yield)
+    }
+
+    /// Returns `true` if the coroutine completed.
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(_))
+    }
+}
+
+// A value the body suspends at or pushed, either moved into the queue or lent from the body
+pub(crate) enum Yielded<Y> {
+    Owned(Y),
+    // Points into the body, only queued while the `yield_ref` future that lent it is alive
+    Lent(NonNull<Y>),
+}
+
+impl<Y> Yielded<Y> {
+    // SAFETY: A lent value must be accessed before the body is polled again or dropped
+    pub(crate) unsafe fn get(&self) -> &Y {
+        match self {
+            Self::Owned(value) => value,
+            // SAFETY: The `yield_ref` future that lent the value keeps it borrowed until it is
+            // polled again or dropped, and removes it from the queue when it is dropped
+            Self::Lent(value) => unsafe { value.as_ref() },
+        }
+    }
+
+    #[track_caller]
+    pub(crate) fn into_owned(self, origin: &Origin) -> Y {
+        match self {
+            Self::Owned(value) => value,
+            Self::Lent(_) => panic!(
+                "{origin} lent a value with `YieldHandle::yield_ref`, which can only be observed \
+                 with `Coroutine::resume_ref_with`",
+            ),
+        }
+    }
+}
+
+impl<Y, T, R> Coroutine<'_, Y, T, R> {
+    /// Resumes the coroutine with a value of type `R` and lends the yielded value to `f` instead
+    /// of moving it out of the coroutine.
+    ///
+    /// This observes values lent with [`YieldHandle::yield_ref`], which stay owned by the body, as
+    /// well as values yielded by value, which are dropped after `f` returns. Returns what `f`
+    /// returned as the yielded value.
+    ///
+    /// ```
+    /// use async_coroutine::{Coroutine, State};
+    ///
+    /// let mut co = Coroutine::new(|handle, ()| async move {
+    ///     let mut buffer = vec![0u8; 1 << 20];
+    ///     for round in 1..=2 {
+    ///         buffer.fill(round);
+    ///         handle.yield_ref(&buffer).await;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(co.resume_ref_with((), |buffer| buffer[0]), State::Yield(1));
+    /// assert_eq!(co.resume_ref_with((), |buffer| buffer.len()), State::Yield(1 << 20));
+    /// assert_eq!(co.resume_ref_with((), |buffer| buffer[0]), State::Complete(()));
+    /// ```
+    #[track_caller]
+    pub fn resume_ref_with<U>(&mut self, resume: R, f: impl FnOnce(&Y) -> U) -> State<U, T> {
+        self.start_resume(resume);
+        // SAFETY: The body is not polled while `f` observes the value
+        self.poll_spinning()
+            .0
+            .map_yield(|value| f(unsafe { value.get() }))
+    }
+
+    /// Resumes the coroutine with a value of type `R` and writes the yielded value to `slot`
+    /// instead of returning it.
+    ///
+    /// This avoids moving large values through [`State`] in hot loops. The previous value of
+    /// `slot` is dropped, it is left untouched if the coroutine completes.
+    ///
+    /// ```
+    /// use async_coroutine::{Generator, Resumed};
+    ///
+    /// let mut generator = Generator::new(|handle, ()| async move {
+    ///     for round in 0..3 {
+    ///         handle.yield_([round; 4096]).await;
+    ///     }
+    /// });
+    ///
+    /// let mut slot = None;
+    /// let mut sum = 0;
+    /// while let Resumed::Yield = generator.resume_into((), &mut slot) {
+    ///     sum += slot.as_ref().unwrap()[0];
+    /// }
+    /// assert_eq!(sum, 3);
+    /// ```
+    #[track_caller]
+    pub fn resume_into(&mut self, resume: R, slot: &mut Option<Y>) -> Resumed<T> {
+        self.start_resume(resume);
+        match self.poll_spinning().0 {
+            State::Yield(value) => {
+                *slot = Some(value.into_owned(&self.yield_handle.shared.origin));
+                Resumed::Yield
+            }
+            State::Complete(value) => Resumed::Complete(value),
+        }
+    }
+
+    #[track_caller]
+    pub(crate) fn owned_state(&self, state: State<Yielded<Y>, T>) -> State<Y, T> {
+        state.map_yield(|value| value.into_owned(&self.yield_handle.shared.origin))
+    }
+}
+
+impl<Y, R> YieldHandle<Y, R> {
+    /// Lends a value to the driver and receives back the resume value when the coroutine is
+    /// resumed, so the body keeps ownership of the value.
+    ///
+    /// The driver has to observe the value with [`Coroutine::resume_ref_with`]. This avoids moving
+    /// large values out of and back into the coroutine. Like [`YieldHandle::yield_`], a resume
+    /// value that was put back is returned without suspending.
+    ///
+    /// # Panics
+    ///
+    /// The resume that receives the value panics if it is not [`Coroutine::resume_ref_with`].
+    pub async fn yield_ref(&self, value: &Y) -> R {
+        self.assert_attached();
+        if let Some(resume) = self.shared.pushback.borrow_mut().pop() {
+            return resume;
+        }
+
+        let value = NonNull::from(value);
+        let _guard = Unlend {
+            handle: self,
+            value,
+        };
+        self.suspend_with(Yielded::Lent(value)).await
+    }
+}
+
+// Removes the lent value from the queue if the `yield_ref` future is dropped before the driver
+// received it, e.g. because it lost a `select`
+struct Unlend<'a, Y, R> {
+    handle: &'a YieldHandle<Y, R>,
+    value: NonNull<Y>,
+}
+
+impl<Y, R> Drop for Unlend<'_, Y, R> {
+    fn drop(&mut self) {
+        // The queue is not borrowed while the body runs or is dropped
+        if let Ok(mut values) = self.handle.shared.values.try_borrow_mut() {
+            values.retain(
+                |queued| !matches!(queued.value, Yielded::Lent(value) if value == self.value),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Resumed;
+    use crate::{Coroutine, State};
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    #[test]
+    fn test_yield_ref() {
+        let mut co = Coroutine::new(|handle, ()| async move {
+            let mut text = String::from("a");
+            handle.yield_ref(&text).await;
+            text.push('b');
+            handle.yield_(text.clone()).await;
+            handle.yield_ref(&text).await;
+            text
+        });
+
+        assert_eq!(
+            co.resume_ref_with((), |text| text.clone()),
+            State::Yield("a".to_string())
+        );
+        assert_eq!(co.resume_ref_with((), |text| text.len()), State::Yield(2));
+        assert_eq!(co.resume_ref_with((), |text| text.len()), State::Yield(2));
+        assert_eq!(co.resume_with(()), State::Complete("ab".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "lent a value with `YieldHandle::yield_ref`")]
+    fn test_yield_ref_resume_with() {
+        let mut co = Coroutine::<_, (), ()>::new(|handle, ()| async move {
+            handle.yield_ref(&1).await;
+        });
+        co.resume_with(());
+    }
+
+    #[test]
+    fn test_yield_ref_dropped() {
+        // The lent value is dropped together with the future that lent it, before the driver
+        // receives it. Otherwise the second yield would panic with the default `YieldPolicy`.
+        let mut co = Coroutine::new(|handle, ()| async move {
+            {
+                let value = String::from("lent");
+                let mut lend = pin!(handle.yield_ref(&value));
+                let mut context = Context::from_waker(Waker::noop());
+                assert_eq!(lend.as_mut().poll(&mut context), Poll::Pending);
+            }
+            handle.yield_("owned".to_string()).await;
+        });
+
+        assert_eq!(
+            co.resume_ref_with((), |value| value.clone()),
+            State::Yield("owned".to_string())
+        );
+        assert_eq!(co.resume_with(()), State::Complete(()));
+    }
+
+    #[test]
+    fn test_resume_into() {
+        let mut co = Coroutine::new(|handle, mut value: i32| async move {
+            while value != 0 {
+                value = handle.yield_(value * 2).await;
+            }
+            "Bye"
+        });
+
+        let mut slot = Some(0);
+        assert_eq!(co.resume_into(1, &mut slot), Resumed::Yield);
+        assert_eq!(slot, Some(2));
+        assert_eq!(co.resume_into(5, &mut slot), Resumed::Yield);
+        assert_eq!(slot, Some(10));
+        assert_eq!(co.resume_into(0, &mut slot), Resumed::Complete("Bye"));
+        assert_eq!(slot, Some(10));
+    }
+}
@@ -1,20 +1,166 @@
 #![deny(rust_2018_idioms)]
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
+// `yield_expr` is checked while parsing, before the tests are configured away
+#![cfg_attr(
+    feature = "nightly",
+    feature(async_iterator, coroutine_trait, yield_expr)
+)]
+#![cfg_attr(all(test, feature = "nightly"), feature(coroutines))]
 
+// Allows the code generated by the proc macros to refer to `::async_coroutine` in the tests
+#[cfg(all(test, feature = "macros"))]
+extern crate self as async_coroutine;
+
+// Emits a debug record if the `log` feature is enabled, compiles to nothing otherwise
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        ::log::debug!($($arg)+)
+    };
+}
+
+pub mod actors;
+pub mod adapters;
+pub mod behavior;
+pub mod drivers;
+pub mod effects;
+pub mod io;
+pub mod registry;
+pub mod replay;
+pub mod script;
+pub mod session;
+pub mod stack;
+pub mod supervision;
+pub mod testing;
+pub mod trace;
+
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(feature = "tokio")]
+pub mod bridge;
+#[cfg(feature = "egui")]
+pub mod egui;
+#[cfg(feature = "embassy")]
+pub mod embassy;
+#[cfg(feature = "critical-section")]
+pub mod interrupt;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "allocator-api2")]
+mod allocator;
+mod builder;
+mod constructors;
+mod error;
 mod executor;
+mod fallible;
+#[cfg(feature = "genawaiter")]
+mod genawaiter_compat;
+mod implicit;
+mod inline;
+mod lend;
+mod macros;
+#[cfg(feature = "nightly")]
+mod nightly;
+mod observer;
+mod pool;
+mod resumable;
+mod static_coroutine;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
 mod yield_now;
 
-use self::executor::Executor;
+pub use self::builder::CoroutineBuilder;
+pub use self::error::{BudgetExceeded, Cancelled, Completed, Throttled, TimedOut};
+pub use self::fallible::{TryCoroutine, TryGenerator, TryState};
+#[cfg(feature = "genawaiter")]
+pub use self::genawaiter_compat::GenawaiterGen;
+pub use self::implicit::yield_value;
+pub use self::inline::InlineCoroutine;
+pub use self::lend::Resumed;
+pub use self::observer::Observer;
+pub use self::pool::{CoroutinePool, Pooled};
+pub use self::resumable::{BoxCoroutine, Resumable};
+pub use self::static_coroutine::{StaticCoroutine, StaticHandle, StaticSlot};
+pub use self::stats::Stats;
+/// The body is moved into the coroutine together with the remaining parameters, so it runs when
+/// the coroutine is first resumed. The initial resume value is dropped.
+///
+/// The attribute has to be used by its path, because the name clashes with the built-in
+/// `#[coroutine]` attribute.
+///
+/// ```
+/// use async_coroutine::{Generator, YieldHandle};
+///
+/// #[async_coroutine::coroutine]
+/// async fn range(handle: YieldHandle<u32>, start: u32, end: u32) -> &'static str {
+///     for value in start..end {
+///         handle.yield_(value).await;
+///     }
+///     "Bye"
+/// }
+///
+/// let generator: Generator<u32, &str> = range(1, 4);
+/// assert_eq!(generator.collect_with_final::<Vec<_>>(), (vec![1, 2, 3], "Bye"));
+/// ```
+#[cfg(feature = "macros")]
+pub use async_coroutine_macros::coroutine;
+/// ```
+/// use async_coroutine::{Generator, yield_types};
+///
+/// yield_types! {
+///     #[derive(Debug, PartialEq)]
+///     pub enum Event {
+///         Progress(f32),
+///         Log(String),
+///     }
+/// }
+///
+/// let generator = Generator::new(|handle, ()| async move {
+///     handle.yield_log("start".to_string()).await;
+///     handle.yield_progress(0.5).await;
+///     handle.yield_(Event::from(1.0)).await;
+/// });
+///
+/// assert_eq!(
+///     generator.collect_with_final::<Vec<_>>().0,
+///     [Event::Log("start".to_string()), Event::Progress(0.5), Event::Progress(1.0)],
+/// );
+/// ```
+#[cfg(feature = "macros")]
+pub use async_coroutine_macros::yield_types;
+
+use self::builder::Config;
+use self::executor::{Body, Executor, Restart, Restartable};
+use self::lend::Yielded;
 use self::yield_now::yield_now;
-use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc, task::Poll};
+use std::{
+    any::Any,
+    cell::{Cell, Ref, RefCell},
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    ops::ControlFlow,
+    panic::Location,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
 
 /// A generator is a coroutine that does not have a resume value.
-pub type Generator<Y, T> = Coroutine<Y, T, ()>;
+pub type Generator<'a, Y, T> = Coroutine<'a, Y, T, ()>;
+
+/// A consumer is a coroutine that does not yield values, it only receives resume values.
+pub type Consumer<'a, T, R> = Coroutine<'a, (), T, R>;
 
 /// Represents the state of a coroutine, which can either yield a value of type `Y` or complete with
 /// a value of type `T`.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State<Y, T> {
     /// The coroutine yielded a value of type `Y`.
     Yield(Y),
@@ -41,6 +187,26 @@ impl<Y, T> State<Y, T> {
         }
     }
 
+    /// Returns `Some` with a mutable reference to the yielded value if the state is [`Yield`],
+    /// otherwise `None`.
+    pub fn as_yield_mut(&mut self) -> Option<&mut Y> {
+        if let Self::Yield(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `Some` with a mutable reference to the completed value if the state is
+    /// [`Complete`], otherwise `None`.
+    pub fn as_complete_mut(&mut self) -> Option<&mut T> {
+        if let Self::Complete(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
     /// Returns `true` if the state is [`Yield`].
     pub fn is_yield(&self) -> bool {
         matches!(self, Self::Yield(..))
@@ -50,6 +216,147 @@ impl<Y, T> State<Y, T> {
     pub fn is_complete(&self) -> bool {
         matches!(self, Self::Complete(..))
     }
+
+    /// Converts the state into `Some` with the yielded value if it is [`Yield`], otherwise `None`.
+    pub fn into_yield(self) -> Option<Y> {
+        if let Self::Yield(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Converts the state into `Some` with the completed value if it is [`Complete`], otherwise
+    /// `None`.
+    pub fn into_complete(self) -> Option<T> {
+        if let Self::Complete(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Maps the yielded value with `f`, leaving a completed value untouched.
+    pub fn map_yield<U>(self, f: impl FnOnce(Y) -> U) -> State<U, T> {
+        match self {
+            Self::Yield(v) => State::Yield(f(v)),
+            Self::Complete(v) => State::Complete(v),
+        }
+    }
+
+    /// Maps the completed value with `f`, leaving a yielded value untouched.
+    pub fn map_complete<U>(self, f: impl FnOnce(T) -> U) -> State<Y, U> {
+        match self {
+            Self::Yield(v) => State::Yield(v),
+            Self::Complete(v) => State::Complete(f(v)),
+        }
+    }
+
+    /// Returns the yielded value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is [`Complete`].
+    #[track_caller]
+    pub fn unwrap_yield(self) -> Y {
+        self.expect_yield("called `State::unwrap_yield()` on a `Complete` state")
+    }
+
+    /// Returns the completed value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is [`Yield`].
+    #[track_caller]
+    pub fn unwrap_complete(self) -> T {
+        self.expect_complete("called `State::unwrap_complete()` on a `Yield` state")
+    }
+
+    /// Returns the yielded value.
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` if the state is [`Complete`].
+    #[track_caller]
+    pub fn expect_yield(self, msg: &str) -> Y {
+        match self {
+            Self::Yield(v) => v,
+            Self::Complete(_) => panic!("{msg}"),
+        }
+    }
+
+    /// Returns the completed value.
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` if the state is [`Yield`].
+    #[track_caller]
+    pub fn expect_complete(self, msg: &str) -> T {
+        match self {
+            Self::Yield(_) => panic!("{msg}"),
+            Self::Complete(v) => v,
+        }
+    }
+
+    /// Returns the yielded value or computes one from the completed value with `f`.
+    pub fn yield_or_else(self, f: impl FnOnce(T) -> Y) -> Y {
+        match self {
+            Self::Yield(v) => v,
+            Self::Complete(v) => f(v),
+        }
+    }
+
+    /// Converts the state into a [`Result`], mapping [`Complete`] to `Ok` and [`Yield`] to `Err`.
+    ///
+    /// This allows using `?` to bail out of a loop as soon as a coroutine completes.
+    pub fn into_result(self) -> Result<T, Y> {
+        match self {
+            Self::Yield(v) => Err(v),
+            Self::Complete(v) => Ok(v),
+        }
+    }
+}
+
+/// Maps [`Yield`](State::Yield) to [`Continue`](ControlFlow::Continue) and
+/// [`Complete`](State::Complete) to [`Break`](ControlFlow::Break).
+impl<Y, T> From<State<Y, T>> for ControlFlow<T, Y> {
+    fn from(state: State<Y, T>) -> Self {
+        match state {
+            State::Yield(v) => Self::Continue(v),
+            State::Complete(v) => Self::Break(v),
+        }
+    }
+}
+
+impl<Y, T> From<ControlFlow<T, Y>> for State<Y, T> {
+    fn from(flow: ControlFlow<T, Y>) -> Self {
+        match flow {
+            ControlFlow::Continue(v) => Self::Yield(v),
+            ControlFlow::Break(v) => Self::Complete(v),
+        }
+    }
+}
+
+/// Maps [`Yield`](State::Yield) to [`Left`](either::Either::Left) and
+/// [`Complete`](State::Complete) to [`Right`](either::Either::Right).
+#[cfg(feature = "either")]
+impl<Y, T> From<State<Y, T>> for either::Either<Y, T> {
+    fn from(state: State<Y, T>) -> Self {
+        match state {
+            State::Yield(v) => Self::Left(v),
+            State::Complete(v) => Self::Right(v),
+        }
+    }
+}
+
+#[cfg(feature = "either")]
+impl<Y, T> From<either::Either<Y, T>> for State<Y, T> {
+    fn from(either: either::Either<Y, T>) -> Self {
+        match either {
+            either::Either::Left(v) => Self::Yield(v),
+            either::Either::Right(v) => Self::Complete(v),
+        }
+    }
 }
 
 impl<T> State<T, T> {
@@ -62,135 +369,1157 @@ impl<T> State<T, T> {
     }
 }
 
-struct ExecutorState<Y, T, R> {
+/// Determines what happens if the body of a coroutine is pending without having yielded a value,
+/// e.g. because it awaits a future that does not go through [`YieldHandle::yield_`].
+///
+/// Only such foreign `Pending`s count as spins, suspending at [`YieldHandle::checkpoint`] does
+/// not. The policy only applies to unbudgeted resumes like [`Coroutine::resume_with`], budgeted
+/// and timed resumes are limited by their budget or timeout instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpinPolicy {
+    /// Keeps polling the body until it yields or completes.
+    Spin,
+    /// Panics after the body was pending the given number of consecutive times without yielding,
+    /// completing or suspending at a checkpoint.
+    Panic(usize),
+}
+
+impl Default for SpinPolicy {
+    fn default() -> Self {
+        Self::Panic(1_000_000)
+    }
+}
+
+/// Determines what happens if a value is yielded while a previously yielded value has not been
+/// returned to the driver yet.
+///
+/// This can happen if multiple [`YieldHandle::yield_`] futures are polled concurrently, e.g. when
+/// joining them, or if a yield future is dropped after its first poll, e.g. when selecting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YieldPolicy {
+    /// Panics.
+    #[default]
+    Panic,
+    /// Replaces the pending value with the new value.
+    Overwrite,
+    /// Queues the new value after the pending value. The driver receives the queued values one per
+    /// resume without polling the body, and the resume values are handed to the pending yields in
+    /// the order the coroutine was resumed.
+    Queue,
+}
+
+/// The result of [`Coroutine::into_completed`].
+#[derive(Debug)]
+// Only linted where the types are known, i.e. in the tests
+#[allow(clippy::large_enum_variant)]
+pub enum IntoCompleted<'a, Y, T, R> {
+    /// The body completed with a value of type `T` that was not handed out yet.
+    Completed(T),
+    /// The completion value was already handed out by a resume.
+    Terminated,
+    /// The body has not completed yet, the coroutine is given back.
+    Pending(Coroutine<'a, Y, T, R>),
+}
+
+struct ExecutorState<'a, Y, T, R> {
     #[expect(clippy::type_complexity)]
-    init: Option<Box<dyn FnOnce(YieldHandle<Y, R>, R) -> Pin<Box<dyn Future<Output = T>>>>>,
-    executor: Option<Executor<T>>,
+    init: Option<Box<dyn FnOnce(YieldHandle<Y, R>, R) -> Body<'a, T> + 'a>>,
+    executor: Option<Executor<'a, T>>,
+    // Used instead of `init` by restartable coroutines
+    factory: Option<Box<dyn Restart<'a, Y, T, R> + 'a>>,
 }
 
-impl<Y, T, R> ExecutorState<Y, T, R>
-where
-    T: 'static,
-{
-    fn init_or_resume(&mut self, yield_handle: &YieldHandle<Y, R>, resume: R) -> &mut Executor<T> {
-        // Can not use match/if-let here because of borrow checker limitations
+impl<'a, Y, T, R> ExecutorState<'a, Y, T, R> {
+    fn new<F>(f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'a) -> Self
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Self::from_init(move |handle, initial_value| {
+            Body::Boxed(Box::pin(f(handle, initial_value)))
+        })
+    }
+
+    fn from_init(init: impl FnOnce(YieldHandle<Y, R>, R) -> Body<'a, T> + 'a) -> Self {
+        Self {
+            init: Some(Box::new(init)),
+            executor: None,
+            factory: None,
+        }
+    }
+
+    fn restartable<F>(f: impl Fn(YieldHandle<Y, R>, R) -> F + 'a) -> Self
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Self {
+            init: None,
+            executor: None,
+            factory: Some(Box::new(Restartable::new(f))),
+        }
+    }
+
+    fn init_or_resume(&mut self, yield_handle: &YieldHandle<Y, R>, resume: R) {
         if self.executor.is_some() {
             // Put resume into place
-            *yield_handle.resume.borrow_mut() = Some(resume);
+            let mut resumes = yield_handle.shared.resumes.borrow_mut();
+            if yield_handle.shared.yield_policy != YieldPolicy::Queue {
+                resumes.clear();
+            }
+            resumes.push_back(resume);
         } else {
             // Initialize executor
-            self.executor = Some(Executor::new(self.init.take().unwrap()(
-                yield_handle.clone_(),
-                resume,
-            )));
+            let handle = yield_handle.clone_();
+            let body = match self.init.take() {
+                Some(init) => init(handle, resume),
+                None => Body::Boxed(self.factory.as_mut().unwrap().start(handle, resume)),
+            };
+            self.executor = Some(Executor::new(body));
         }
+    }
 
-        self.executor.as_mut().unwrap()
+    // Drops the body of a restartable coroutine, keeping its allocation for the next one
+    fn recycle(&mut self) {
+        if let Some(executor) = self.executor.take() {
+            self.factory.as_mut().unwrap().recycle(executor);
+        }
     }
 }
 
 /// A coroutine that can yield values of type `Y`, can be resumed with a value of type `R` and
 /// completes with a value of type `T`.
-pub struct Coroutine<Y, T, R> {
-    executor: ExecutorState<Y, T, R>,
+///
+/// The body may borrow data that lives for `'a`, e.g. to complete with a reference into it.
+pub struct Coroutine<'a, Y, T, R> {
+    executor: ExecutorState<'a, Y, T, R>,
     yield_handle: YieldHandle<Y, R>,
+    in_progress: bool,
+    // The handle `yield_value` uses, if enabled
+    implicit_handle: Option<Rc<dyn Any>>,
+    stats: Stats,
+    complete: Option<T>,
+    completed: bool,
+    // Set while the body is polled, so it stays set if the body panics
+    poisoned: bool,
+    // The number of values the coroutine is still expected to yield, see `size_hint`
+    remaining_hint: Option<usize>,
+    config: Config<'a, Y, T, R>,
 }
 
-impl<Y, T, R> Coroutine<Y, T, R>
-where
-    T: 'static,
-{
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
     /// Creates a new coroutine from a function that takes the [`YieldHandle`] and the initial
     /// value. The function must return a future that resolves to the final value of type `T`.
-    pub fn new<F>(f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'static) -> Self
+    #[track_caller]
+    pub fn new<F>(f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'a) -> Self
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Self::builder().build(f)
+    }
+
+    /// Creates a restartable coroutine, which keeps the function around so that
+    /// [`Coroutine::reset`] can run the body again from the beginning.
+    #[track_caller]
+    pub fn restartable<F>(f: impl Fn(YieldHandle<Y, R>, R) -> F + 'a) -> Self
+    where
+        F: Future<Output = T> + 'a,
+    {
+        Self::builder().build_restartable(f)
+    }
+
+    /// Creates a new coroutine like [`Coroutine::new`], whose body can access `context` with
+    /// [`YieldHandle::context`] for its entire lifetime.
+    ///
+    /// This is meant for long-lived services like an asset database or a random number generator,
+    /// which should not be passed with every resume value. Use an `Rc` to share a context between
+    /// multiple coroutines.
+    ///
+    /// ```
+    /// use async_coroutine::{Generator, State};
+    /// use std::{cell::Cell, rc::Rc};
+    ///
+    /// struct Services {
+    ///     next_id: Cell<u32>,
+    /// }
+    ///
+    /// let services = Rc::new(Services { next_id: Cell::new(1) });
+    /// let mut generator = Generator::new_with_context(Rc::clone(&services), |handle, ()| async move {
+    ///     let services = handle.context::<Rc<Services>>();
+    ///     let id = services.next_id.replace(services.next_id.get() + 1);
+    ///     handle.yield_(id).await;
+    /// });
+    ///
+    /// assert_eq!(generator.resume(), State::Yield(1));
+    /// assert_eq!(services.next_id.get(), 2);
+    /// ```
+    #[track_caller]
+    pub fn new_with_context<C, F>(
+        context: C,
+        f: impl FnOnce(YieldHandle<Y, R>, R) -> F + 'a,
+    ) -> Self
     where
-        F: Future<Output = T> + 'static,
+        C: 'static,
+        F: Future<Output = T> + 'a,
     {
+        Self::builder().context(context).build(f)
+    }
+
+    /// Returns a [`CoroutineBuilder`] for configuring a coroutine before creating it.
+    pub fn builder() -> CoroutineBuilder<'a, Y, T, R> {
+        CoroutineBuilder::new()
+    }
+
+    #[track_caller]
+    fn with_config(executor: ExecutorState<'a, Y, T, R>, mut config: Config<'a, Y, T, R>) -> Self {
+        let yield_handle = YieldHandle {
+            shared: Rc::new(Shared {
+                values: RefCell::new(VecDeque::new()),
+                resumes: RefCell::new(VecDeque::new()),
+                pushback: RefCell::new(Vec::new()),
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+                polling: Cell::new(false),
+                checkpoints: Cell::new(0),
+                heartbeat_since: Cell::new(None),
+                checkpointed: Cell::new(false),
+                deadline: Cell::new(None),
+                fuel: Cell::new(None),
+                yield_policy: config.yield_policy,
+                yield_interval: config.yield_interval,
+                context: config.context.take(),
+                registration: config
+                    .register
+                    .then(|| registry::register(config.name.clone(), Location::caller())),
+                origin: Origin {
+                    name: config.name.take(),
+                    location: Location::caller(),
+                },
+            }),
+        };
         Self {
-            executor: ExecutorState {
-                init: Some(Box::new(move |handle, initial_value| {
-                    Box::pin(f(handle, initial_value))
-                })),
-                executor: None,
-            },
-            yield_handle: YieldHandle {
-                value: Rc::new(RefCell::new(None)),
-                resume: Rc::new(RefCell::new(None)),
-            },
+            executor,
+            implicit_handle: config.implicit_handle.map(|f| f(&yield_handle)),
+            yield_handle,
+            in_progress: false,
+            stats: Stats::default(),
+            complete: None,
+            completed: false,
+            poisoned: false,
+            remaining_hint: config.size_hint,
+            config,
+        }
+    }
+
+    /// Returns the name of the coroutine, if it was given one via [`CoroutineBuilder::name`].
+    pub fn name(&self) -> Option<&str> {
+        self.yield_handle.shared.origin.name.as_deref()
+    }
+
+    /// Returns the location in the source code where the coroutine was created.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.yield_handle.shared.origin.location
+    }
+
+    /// Returns `true` if the body of the coroutine panicked while it was resumed.
+    ///
+    /// A poisoned coroutine can not be resumed anymore, but a restartable one can be recovered with
+    /// [`Coroutine::reset`].
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Returns `true` if the coroutine can be reset with [`Coroutine::reset`].
+    pub fn is_restartable(&self) -> bool {
+        self.executor.factory.is_some()
+    }
+
+    /// Drops the running body of a restartable coroutine, so the next resume runs it again from
+    /// the beginning with a new initial value.
+    ///
+    /// The configuration, observers and statistics are kept, and the allocation of the body is
+    /// reused for the next one. If the body is suspended, observers are notified like when the
+    /// coroutine is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine is not restartable, see [`Coroutine::restartable`].
+    #[track_caller]
+    pub fn reset(&mut self) {
+        assert!(
+            self.is_restartable(),
+            "{} is not restartable",
+            self.yield_handle.shared.origin,
+        );
+        if self.executor.executor.is_some() && !self.completed {
+            for observer in &mut self.config.observers {
+                observer.on_drop_suspended();
+            }
+        }
+
+        self.executor.recycle();
+        let shared = &self.yield_handle.shared;
+        shared.values.borrow_mut().clear();
+        shared.resumes.borrow_mut().clear();
+        shared.pushback.borrow_mut().clear();
+        shared.checkpoints.set(0);
+        shared.heartbeat_since.set(None);
+        self.in_progress = false;
+        self.complete = None;
+        self.completed = false;
+        self.poisoned = false;
+        self.remaining_hint = self.config.size_hint;
+        self.update_registration();
+    }
+
+    /// Returns the runtime statistics of the coroutine.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Returns `true` if the coroutine has completed and handed out its completion value, so it
+    /// must not be resumed anymore.
+    pub fn is_terminated(&self) -> bool {
+        self.completed
+            && self.complete.is_none()
+            && self.yield_handle.shared.values.borrow().is_empty()
+    }
+
+    /// Returns the bounds on the number of values the coroutine will still yield, like
+    /// [`Iterator::size_hint`].
+    ///
+    /// The lower bound is based on the hint set with [`CoroutineBuilder::size_hint`], it is zero
+    /// if there is none. The upper bound is only known once the coroutine has completed.
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        let queued = self.yield_handle.shared.values.borrow().len();
+        if self.completed {
+            (queued, Some(queued))
+        } else {
+            (self.remaining_hint.unwrap_or(0).max(queued), None)
         }
     }
 
+    /// Sets the [`SpinPolicy`] of the coroutine.
+    pub fn with_spin_policy(mut self, spin_policy: SpinPolicy) -> Self {
+        self.config.spin_policy = spin_policy;
+        self
+    }
+
     /// Resumes the coroutine with a value of type `R`.
+    #[track_caller]
     pub fn resume_with(&mut self, resume: R) -> State<Y, T> {
-        // Get executor
-        let executor = self.executor.init_or_resume(&self.yield_handle, resume);
+        self.start_resume(resume);
+        let state = self.poll_spinning().0;
+        self.owned_state(state)
+    }
+
+    /// Resumes the coroutine with a value of type `R` and drains every value it produces until it
+    /// suspends again.
+    ///
+    /// This includes the values pushed with [`YieldHandle::push`] as well as the value of the
+    /// [`YieldHandle::yield_`] the coroutine suspends at. Returns the completion value as well if
+    /// the coroutine completed.
+    #[track_caller]
+    pub fn resume_batch(&mut self, resume: R) -> (Vec<Y>, Option<T>) {
+        self.start_resume(resume);
+        let mut values = Vec::new();
+        loop {
+            match self.poll_spinning() {
+                (State::Yield(value), suspended) => {
+                    values.push(value.into_owned(&self.yield_handle.shared.origin));
+                    if suspended {
+                        break (values, None);
+                    }
+                }
+                (State::Complete(value), _) => break (values, Some(value)),
+            }
+        }
+    }
 
-        // Loop step
+    // Polls until a state is produced, also returns whether the coroutine is suspended
+    #[track_caller]
+    fn poll_spinning(&mut self) -> (State<Yielded<Y>, T>, bool) {
+        let mut polls = 0;
         loop {
-            let state = match executor.poll() {
-                Poll::Ready(res) => Some(State::Complete(res)),
-                Poll::Pending => self
-                    .yield_handle
-                    .value
-                    .borrow_mut()
-                    .take()
-                    .map(State::Yield),
+            if let Some(step) = self.poll_queued(None) {
+                break step;
+            }
+            if self.yield_handle.shared.checkpointed.get() {
+                polls = 0;
+                continue;
+            }
+
+            polls += 1;
+            if let SpinPolicy::Panic(max_polls) = self.config.spin_policy {
+                assert!(
+                    polls < max_polls,
+                    "{} was polled {polls} times without yielding or completing, it probably \
+                     awaits a future that does not go through `YieldHandle::yield_` or \
+                     `YieldHandle::checkpoint`",
+                    self.yield_handle.shared.origin,
+                );
+            }
+        }
+    }
+
+    /// Resumes the coroutine with a value of type `R`, but gives up after the body has been polled
+    /// `max_polls` times without yielding or completing.
+    ///
+    /// This protects the caller from bodies that await futures which never cooperate. If the
+    /// budget is exceeded, the resume stays in progress and can be continued with
+    /// [`Coroutine::poll_with_budget`].
+    #[track_caller]
+    pub fn resume_with_budget(
+        &mut self,
+        resume: R,
+        max_polls: usize,
+    ) -> Result<State<Y, T>, BudgetExceeded> {
+        self.start_resume(resume);
+        self.poll_with_budget(max_polls)
+    }
+
+    /// Continues a resume that was interrupted because its budget was exceeded, polling the body
+    /// at most `max_polls` more times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no resume is in progress.
+    #[track_caller]
+    pub fn poll_with_budget(&mut self, max_polls: usize) -> Result<State<Y, T>, BudgetExceeded> {
+        assert!(
+            self.in_progress,
+            "no resume of {} is in progress",
+            self.yield_handle.shared.origin,
+        );
+        for _ in 0..max_polls {
+            if let Some(state) = self.poll_step(None) {
+                return Ok(state);
+            }
+        }
+        Err(BudgetExceeded)
+    }
+
+    /// Resumes the coroutine with a value of type `R`, but gives up once `timeout` has passed
+    /// without the body yielding or completing.
+    ///
+    /// This protects the caller from bodies that await futures which never complete. The clock is
+    /// checked between polls, so a body that blocks inside a single poll can not be interrupted.
+    /// If the deadline passes, the resume stays in progress and can be continued with
+    /// [`Coroutine::poll_with_timeout`].
+    #[track_caller]
+    pub fn resume_with_timeout(
+        &mut self,
+        resume: R,
+        timeout: Duration,
+    ) -> Result<State<Y, T>, TimedOut> {
+        self.start_resume(resume);
+        self.poll_with_timeout(timeout)
+    }
+
+    /// Continues a resume that was interrupted because it timed out, polling the body for at most
+    /// `timeout` more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no resume is in progress.
+    #[track_caller]
+    pub fn poll_with_timeout(&mut self, timeout: Duration) -> Result<State<Y, T>, TimedOut> {
+        assert!(
+            self.in_progress,
+            "no resume of {} is in progress",
+            self.yield_handle.shared.origin,
+        );
+        let deadline = Instant::now() + timeout;
+        self.yield_handle.shared.deadline.set(Some(deadline));
+        let result = loop {
+            if let Some(state) = self.poll_step(None) {
+                break Ok(state);
+            }
+            if Instant::now() >= deadline {
+                break Err(TimedOut);
+            }
+        };
+        self.yield_handle.shared.deadline.set(None);
+        result
+    }
+
+    /// Runs the coroutine for a time slice of at most `slice`, until it yields or completes.
+    ///
+    /// If the previous slice ran out, the interrupted resume is continued and `resume` is not
+    /// called, otherwise a new resume is started with the value returned by `resume`. This allows
+    /// spreading the work of a resume over multiple frames, e.g. by calling it once per frame. The
+    /// clock is checked between polls, so long computations should contain
+    /// [checkpoints](YieldHandle::checkpoint).
+    ///
+    /// ```
+    /// use async_coroutine::{Generator, State};
+    /// use std::time::Duration;
+    ///
+    /// let mut generator = Generator::new(|handle, ()| async move {
+    ///     let mut sum = 0u64;
+    ///     for value in 0..10_000 {
+    ///         sum += value;
+    ///         handle.checkpoint().await;
+    ///     }
+    ///     handle.yield_(sum).await;
+    /// });
+    ///
+    /// let sum = loop {
+    ///     if let Ok(State::Yield(sum)) = generator.resume_for(Duration::from_micros(500), || ()) {
+    ///         break sum;
+    ///     }
+    ///     // Render the frame...
+    /// };
+    /// assert_eq!(sum, 49_995_000);
+    /// ```
+    #[track_caller]
+    pub fn resume_for(
+        &mut self,
+        slice: Duration,
+        resume: impl FnOnce() -> R,
+    ) -> Result<State<Y, T>, TimedOut> {
+        if !self.in_progress {
+            self.start_resume(resume());
+        }
+        self.poll_with_timeout(slice)
+    }
+
+    /// Resumes the coroutine with a value of type `R` and gives it `fuel` units of fuel.
+    ///
+    /// Every call of a checkpoint like [`YieldHandle::maybe_yield`] or [`YieldHandle::yield_every`]
+    /// consumes one unit. Once the fuel is used up, the next checkpoint yields its heartbeat. Unlike
+    /// a [time slice](Coroutine::resume_for), this divides the work deterministically.
+    #[track_caller]
+    pub fn resume_with_fuel(&mut self, resume: R, fuel: usize) -> State<Y, T> {
+        self.yield_handle.shared.fuel.set(Some(fuel));
+        let state = self.resume_with(resume);
+        self.yield_handle.shared.fuel.set(None);
+        state
+    }
+
+    /// Returns the completion value if the body of the coroutine has completed but the value has
+    /// not been handed out by a resume yet, otherwise tells whether the coroutine has terminated or
+    /// gives it back.
+    ///
+    /// The value has not been handed out if the body completed while values pushed with
+    /// [`YieldHandle::push`] are still queued. These values are dropped.
+    pub fn into_completed(mut self) -> IntoCompleted<'a, Y, T, R> {
+        match self.complete.take() {
+            Some(complete) => IntoCompleted::Completed(complete),
+            None if self.completed => IntoCompleted::Terminated,
+            None => IntoCompleted::Pending(self),
+        }
+    }
+
+    /// Performs the first resume right away, so the next resume continues after the first yield.
+    ///
+    /// Returns the started coroutine together with the value it first yielded, or the completion
+    /// value if the body completed without yielding. This is useful for handing out coroutines
+    /// that already ran their setup, e.g. consumers that yield once to signal they are ready.
+    ///
+    /// ```
+    /// use async_coroutine::{Coroutine, State};
+    ///
+    /// // The initial value is not used, the lines are received from the following resumes
+    /// let (mut logger, ready) = Coroutine::new(|handle, _: &str| async move {
+    ///     let mut lines = Vec::new();
+    ///     loop {
+    ///         let line = handle.yield_(lines.len()).await;
+    ///         if line.is_empty() {
+    ///             break lines;
+    ///         }
+    ///         lines.push(line);
+    ///     }
+    /// })
+    /// .prime("")
+    /// .unwrap();
+    ///
+    /// assert_eq!(ready, 0);
+    /// assert_eq!(logger.resume_with("first"), State::Yield(1));
+    /// assert_eq!(logger.resume_with(""), State::Complete(vec!["first"]));
+    /// ```
+    #[track_caller]
+    pub fn prime(mut self, initial: R) -> Result<(Self, Y), T> {
+        match self.resume_with(initial) {
+            State::Yield(value) => Ok((self, value)),
+            State::Complete(value) => Err(value),
+        }
+    }
+
+    #[track_caller]
+    fn assert_not_poisoned(&self) {
+        assert!(
+            !self.poisoned,
+            "{} was resumed after its body panicked",
+            self.yield_handle.shared.origin,
+        );
+    }
+
+    #[track_caller]
+    fn start_resume(&mut self, resume: R) {
+        self.assert_not_poisoned();
+        assert!(
+            !self.in_progress,
+            "{} was resumed while a previous resume is still in progress",
+            self.yield_handle.shared.origin,
+        );
+        self.in_progress = true;
+        debug!(
+            "coroutine {}: resume #{}",
+            self.name().unwrap_or("<unnamed>"),
+            self.stats.resumes
+        );
+        self.stats.resumes += 1;
+
+        let started = self.executor.executor.is_some();
+        for observer in &mut self.config.observers {
+            if !started {
+                observer.on_start();
+            }
+            observer.on_resume(&resume);
+        }
+
+        self.executor.init_or_resume(&self.yield_handle, resume);
+        self.update_registration();
+    }
+
+    // Polls once, waker-aware drivers pass the waker of the task that awaits the resume, so futures
+    // the body awaits can wake it
+    #[track_caller]
+    fn poll_step(&mut self, waker: Option<&Waker>) -> Option<State<Y, T>> {
+        let (state, _) = self.poll_queued(waker)?;
+        Some(self.owned_state(state))
+    }
+
+    // Also returns whether the coroutine is suspended, i.e. whether the state is not a pushed value
+    #[track_caller]
+    fn poll_queued(&mut self, waker: Option<&Waker>) -> Option<(State<Yielded<Y>, T>, bool)> {
+        // Hand out queued values without polling
+        let mut queued = self.yield_handle.shared.values.borrow_mut().pop_front();
+        if queued.is_none() && self.complete.is_none() {
+            assert!(
+                !self.completed,
+                "{} was resumed after completion",
+                self.yield_handle.shared.origin,
+            );
+            self.assert_not_poisoned();
+
+            let start = Instant::now();
+            let executor = self.executor.executor.as_mut().unwrap();
+            let waker = waker.unwrap_or(Waker::noop());
+            self.yield_handle.shared.checkpointed.set(false);
+            self.poisoned = true;
+            let poll = {
+                let _polling = Polling::enter(&self.yield_handle.shared);
+                implicit::scope(self.implicit_handle.as_ref(), || executor.poll(waker))
             };
-            if let Some(state) = state {
-                break state;
+            self.poisoned = false;
+            self.stats.polls += 1;
+            self.stats.busy_time += start.elapsed();
+            match poll {
+                // Queued values are handed out before the completion value
+                Poll::Ready(res) => {
+                    self.complete = Some(res);
+                    self.completed = true;
+                }
+                Poll::Pending => (),
+            }
+            queued = self.yield_handle.shared.values.borrow_mut().pop_front();
+        }
+        let step = match queued {
+            Some(queued) => Some((State::Yield(queued.value), queued.suspends)),
+            None => self.complete.take().map(|res| (State::Complete(res), true)),
+        };
+        if let Some((state, _)) = &step {
+            self.in_progress = false;
+            match state {
+                State::Yield(value) => {
+                    // SAFETY: The body has not been polled since the value was handed out
+                    let value = unsafe { value.get() };
+                    self.stats.yields += 1;
+                    if let Some(remaining) = &mut self.remaining_hint {
+                        *remaining = remaining.saturating_sub(1);
+                    }
+                    debug!(
+                        "coroutine {}: yielded at resume #{}",
+                        self.name().unwrap_or("<unnamed>"),
+                        self.stats.resumes - 1
+                    );
+                    if let Some(on_yield) = &mut self.config.on_yield {
+                        on_yield(value);
+                    }
+                    for observer in &mut self.config.observers {
+                        observer.on_yield(value);
+                    }
+                }
+                State::Complete(value) => {
+                    debug!(
+                        "coroutine {}: completed at resume #{}",
+                        self.name().unwrap_or("<unnamed>"),
+                        self.stats.resumes - 1
+                    );
+                    for observer in &mut self.config.observers {
+                        observer.on_complete(value);
+                    }
+                }
+            }
+        }
+        self.update_registration();
+        step
+    }
+}
+
+impl<Y, T, R> Resumable<R> for Coroutine<'_, Y, T, R> {
+    type Yield = Y;
+    type Complete = T;
+
+    #[track_caller]
+    fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        Coroutine::resume_with(self, resume)
+    }
+}
+
+// The completion value is never pinned
+impl<Y, T, R> Unpin for Coroutine<'_, Y, T, R> {}
+
+impl<Y, T, R> Drop for Coroutine<'_, Y, T, R> {
+    fn drop(&mut self) {
+        if self.executor.executor.is_some() && !self.completed {
+            for observer in &mut self.config.observers {
+                observer.on_drop_suspended();
+            }
+        }
+
+        // Once the body is dropped, the coroutine holds the only handle, unless one escaped
+        #[cfg(debug_assertions)]
+        {
+            self.executor.executor = None;
+            self.executor.init = None;
+            self.executor.factory = None;
+            self.implicit_handle = None;
+            if Rc::strong_count(&self.yield_handle.shared) > 1 && !std::thread::panicking() {
+                panic!(
+                    "{} was dropped while its yield handle is still alive, the handle probably \
+                     escaped from the body",
+                    self.yield_handle.shared.origin,
+                );
             }
         }
     }
 }
 
-impl<Y, T> Generator<Y, T>
+impl<Y, T, R> Coroutine<'_, Y, T, R>
 where
-    T: 'static,
+    R: Default,
 {
-    /// Resumes the generator.
+    /// Resumes the coroutine with the default resume value, e.g. `()` for generators.
+    #[track_caller]
     pub fn resume(&mut self) -> State<Y, T> {
-        self.resume_with(())
+        self.resume_with(R::default())
+    }
+}
+
+impl<Y, T, R> fmt::Debug for Coroutine<'_, Y, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.completed {
+            "completed"
+        } else if self.executor.executor.is_none() {
+            "created"
+        } else {
+            "suspended"
+        };
+        f.debug_struct("Coroutine")
+            .field("name", &self.yield_handle.shared.origin.name)
+            .field("status", &status)
+            .field("resumes", &self.stats.resumes)
+            .finish_non_exhaustive()
     }
 }
 
 /// The yield handle can be used from within the coroutine to yield values and receive a resume
 /// value when the coroutine is resumed.
 pub struct YieldHandle<Y, R = ()> {
-    value: Rc<RefCell<Option<Y>>>,
-    resume: Rc<RefCell<Option<R>>>,
+    shared: Rc<Shared<Y, R>>,
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // The id of the coroutine whose body is being polled on this thread
+    static POLLING: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+struct Shared<Y, R> {
+    values: RefCell<VecDeque<Queued<Y>>>,
+    resumes: RefCell<VecDeque<R>>,
+    // Resume values put back with `YieldHandle::unresume`, the last one is received first
+    pushback: RefCell<Vec<R>>,
+    // Identifies the coroutine in `POLLING`
+    id: usize,
+    // Whether the body is being polled by a resume of the coroutine
+    polling: Cell<bool>,
+    // Calls of `YieldHandle::maybe_yield` since the body last suspended
+    checkpoints: Cell<usize>,
+    // The first call of `YieldHandle::yield_every` since the body last suspended
+    heartbeat_since: Cell<Option<Instant>>,
+    // Set when the last poll of the body suspended at `YieldHandle::checkpoint`, so its `Pending`
+    // is not a foreign one
+    checkpointed: Cell<bool>,
+    // The deadline of a timed resume, which makes `YieldHandle::maybe_yield` suspend once passed
+    deadline: Cell<Option<Instant>>,
+    // The fuel left for the current resume, consumed by the checkpoints of `YieldHandle`
+    fuel: Cell<Option<usize>>,
+    yield_policy: YieldPolicy,
+    yield_interval: Option<usize>,
+    context: Option<Box<dyn Any>>,
+    registration: Option<Rc<RefCell<registry::Entry>>>,
+    origin: Origin,
+}
+
+impl<Y, R> fmt::Debug for YieldHandle<Y, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = &self.shared;
+        f.debug_struct("YieldHandle")
+            .field("name", &shared.origin.name)
+            .field("pending_value", &!shared.values.borrow().is_empty())
+            .field(
+                "pending_resume",
+                &(!shared.resumes.borrow().is_empty() || !shared.pushback.borrow().is_empty()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+// Marks the body of a coroutine as being polled, until it is dropped or the body panics
+struct Polling<'a, Y, R> {
+    shared: &'a Shared<Y, R>,
+    outer: Option<usize>,
+}
+
+impl<'a, Y, R> Polling<'a, Y, R> {
+    fn enter(shared: &'a Shared<Y, R>) -> Self {
+        shared.polling.set(true);
+        Self {
+            shared,
+            outer: POLLING.replace(Some(shared.id)),
+        }
+    }
+}
+
+impl<Y, R> Drop for Polling<'_, Y, R> {
+    fn drop(&mut self) {
+        POLLING.set(self.outer);
+        self.shared.polling.set(false);
+        if std::thread::panicking()
+            && let Some(entry) = &self.shared.registration
+        {
+            entry.borrow_mut().status = registry::Status::Poisoned;
+        }
+    }
+}
+
+// Identifies a coroutine in panic messages
+struct Origin {
+    name: Option<String>,
+    location: &'static Location<'static>,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "coroutine `{name}` (created at {})", self.location),
+            None => write!(f, "coroutine (created at {})", self.location),
+        }
+    }
+}
+
+struct Queued<Y> {
+    value: Yielded<Y>,
+    // Whether the coroutine suspends at this value, `false` for pushed values
+    suspends: bool,
+}
+
+impl<R> YieldHandle<(), R> {
+    /// Suspends the consumer and receives the next resume value.
+    ///
+    /// This is a shorthand for yielding `()`.
+    pub async fn recv(&self) -> R {
+        self.yield_(()).await
+    }
+
+    /// Receives the next resume value like [`YieldHandle::recv`], but leaves it in place, so the
+    /// next receive returns it again.
+    ///
+    /// The returned guard borrows the put back values, so it must be dropped before the consumer
+    /// receives or puts back values again.
+    pub async fn peek_resume(&self) -> Ref<'_, R> {
+        if self.shared.pushback.borrow().is_empty() {
+            let resume = self.recv().await;
+            self.unresume(resume);
+        }
+        Ref::map(self.shared.pushback.borrow(), |pushback| {
+            pushback.last().unwrap()
+        })
+    }
 }
 
 impl<Y, R> YieldHandle<Y, R> {
     /// Yields a value and receives back the resume value when the coroutine is resumed.
+    ///
+    /// If a resume value was put back with [`YieldHandle::unresume`], it is returned instead
+    /// without suspending and the value is dropped.
     pub async fn yield_(&self, value: Y) -> R {
+        self.assert_attached();
+        if let Some(resume) = self.shared.pushback.borrow_mut().pop() {
+            return resume;
+        }
+
+        self.suspend_with(Yielded::Owned(value)).await
+    }
+
+    // Queues the value the body suspends at and receives back the resume value
+    async fn suspend_with(&self, value: Yielded<Y>) -> R {
         // Extra scope necessary because of a false positive of clippy::await_holding_refcell_ref
         {
             // Set current
-            let mut current = self.value.borrow_mut();
-            match *current {
-                Some(_) => panic!("multiple values were yielded without awaiting them"),
-                None => *current = Some(value),
+            let mut current = self.shared.values.borrow_mut();
+            if current.iter().any(|queued| queued.suspends) {
+                match self.shared.yield_policy {
+                    YieldPolicy::Panic => panic!(
+                        "{}: multiple values were yielded without awaiting them",
+                        self.shared.origin,
+                    ),
+                    YieldPolicy::Overwrite => current.retain(|queued| !queued.suspends),
+                    YieldPolicy::Queue => (),
+                }
             }
+            current.push_back(Queued {
+                value,
+                suspends: true,
+            });
 
             // Drop current ref before yield
             drop(current);
         }
+        self.shared.checkpoints.set(0);
+        self.shared.heartbeat_since.set(None);
 
         // Yield one "tick"
         yield_now().await;
+        self.assert_attached();
 
         // Get resume value
-        self.resume
+        self.shared
+            .resumes
             .borrow_mut()
-            .take()
-            .expect("expected resume value")
+            .pop_front()
+            .unwrap_or_else(|| panic!("{}: expected resume value", self.shared.origin))
     }
 
-    // Private so that the user can not clone the handle
-    fn clone_(&self) -> Self {
-        Self {
-            value: Rc::clone(&self.value),
-            resume: Rc::clone(&self.resume),
+    /// Puts a resume value back, so the next [`YieldHandle::yield_`] (or [`YieldHandle::recv`])
+    /// returns it without suspending.
+    ///
+    /// Values that are put back are received in reverse order, i.e. the last one first.
+    pub fn unresume(&self, resume: R) {
+        self.shared.pushback.borrow_mut().push(resume);
+    }
+
+    /// Queues a value without suspending the coroutine.
+    ///
+    /// Pushed values are handed to the driver in order, one per resume, before the value the
+    /// coroutine suspends at next. They do not consume resume values, so the next
+    /// [`YieldHandle::yield_`] receives the most recent resume value (or, with
+    /// [`YieldPolicy::Queue`], the oldest one that was not received yet). Use
+    /// [`Coroutine::resume_batch`] to drain all of them at once.
+    pub fn push(&self, value: Y) {
+        self.assert_attached();
+        self.shared.values.borrow_mut().push_back(Queued {
+            value: Yielded::Owned(value),
+            suspends: false,
+        });
+    }
+
+    /// Yields a batch of values in one suspension and receives back the resume value when the
+    /// coroutine is resumed.
+    ///
+    /// All values but the last are pushed with [`YieldHandle::push`] and the coroutine suspends at
+    /// the last one, so the driver can drain the whole batch with [`Coroutine::resume_batch`].
+    /// Returns `None` without suspending if there are no values.
+    pub async fn yield_many(&self, values: impl IntoIterator<Item = Y>) -> Option<R> {
+        let mut values = values.into_iter().peekable();
+        while let Some(value) = values.next() {
+            if values.peek().is_none() {
+                return Some(self.yield_(value).await);
+            }
+            self.push(value);
+        }
+        None
+    }
+
+    /// Returns the context the coroutine was created with, see [`Coroutine::new_with_context`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine was created without a context or with a context of another type.
+    #[track_caller]
+    pub fn context<C>(&self) -> &C
+    where
+        C: 'static,
+    {
+        let context = self
+            .shared
+            .context
+            .as_ref()
+            .unwrap_or_else(|| panic!("{} was created without a context", self.shared.origin));
+        context.downcast_ref().unwrap_or_else(|| {
+            panic!(
+                "the context of {} is not a `{}`",
+                self.shared.origin,
+                std::any::type_name::<C>(),
+            )
+        })
+    }
+
+    /// Suspends the body for a single poll without yielding a value.
+    ///
+    /// Drivers that limit a resume, like [`Coroutine::resume_for`], check their limit between
+    /// polls, so a checkpoint in a long computation lets them interrupt it. Unlimited resumes
+    /// poll the body again right away, checkpoints do not count towards the [`SpinPolicy`].
+    pub async fn checkpoint(&self) {
+        self.assert_attached();
+        self.shared.checkpointed.set(true);
+        yield_now().await;
+    }
+
+    // Panics if the handle is used outside of a resume of its coroutine, e.g. because it escaped
+    // into a future that is polled by another executor or into the body of another coroutine
+    #[track_caller]
+    fn assert_attached(&self) {
+        assert!(
+            self.shared.polling.get(),
+            "{}: the yield handle was used outside of a resume of its coroutine, it probably \
+             escaped into a future that is polled elsewhere or outlived the coroutine",
+            self.shared.origin,
+        );
+        assert!(
+            POLLING.get() == Some(self.shared.id),
+            "{}: the yield handle was used while another coroutine is being resumed, it was \
+             probably moved into the body of that coroutine",
+            self.shared.origin,
+        );
+    }
+
+    // Private so that the user can not clone the handle
+    fn clone_(&self) -> Self {
+        Self {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+}
+
+impl<Y, R> YieldHandle<Y, R>
+where
+    Y: Default,
+{
+    /// A cooperative checkpoint for long computations, which yields `Y::default()` as a heartbeat
+    /// only if it is due and otherwise returns `None` right away.
+    ///
+    /// The heartbeat is due on every n-th call since the body last suspended, if an interval was
+    /// set with [`CoroutineBuilder::yield_interval`], whenever the deadline of a timed resume like
+    /// [`Coroutine::resume_for`] has passed, and once the fuel given with
+    /// [`Coroutine::resume_with_fuel`] is used up. Returns the resume value if it suspended.
+    ///
+    /// ```
+    /// use async_coroutine::{Generator, State};
+    ///
+    /// let mut generator = Generator::<Option<u64>, _>::builder()
+    ///     .yield_interval(1000)
+    ///     .build(|handle, ()| async move {
+    ///         let mut sum = 0;
+    ///         for value in 0..2500 {
+    ///             sum += value;
+    ///             handle.maybe_yield().await;
+    ///         }
+    ///         handle.yield_(Some(sum)).await;
+    ///     });
+    ///
+    /// assert_eq!(generator.resume(), State::Yield(None));
+    /// assert_eq!(generator.resume(), State::Yield(None));
+    /// assert_eq!(generator.resume(), State::Yield(Some(3_123_750)));
+    /// ```
+    pub async fn maybe_yield(&self) -> Option<R> {
+        self.assert_attached();
+        let shared = &self.shared;
+        let checkpoints = shared.checkpoints.get() + 1;
+        let due = self.consume_fuel()
+            || shared
+                .yield_interval
+                .is_some_and(|interval| checkpoints >= interval)
+            || shared
+                .deadline
+                .get()
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if due {
+            Some(self.yield_(Y::default()).await)
+        } else {
+            shared.checkpoints.set(checkpoints);
+            None
+        }
+    }
+
+    /// A cooperative checkpoint for long computations, which yields `Y::default()` as a heartbeat
+    /// only if more than `interval` has elapsed since the body last suspended, and otherwise
+    /// returns `None` right away.
+    ///
+    /// The time is measured from the first call after the body last suspended, so the clock is
+    /// only read by the body while it uses this checkpoint. The heartbeat is also due once the fuel
+    /// given with [`Coroutine::resume_with_fuel`] is used up. Returns the resume value if it
+    /// suspended.
+    ///
+    /// ```
+    /// use async_coroutine::{Generator, State};
+    /// use std::time::Duration;
+    ///
+    /// let generator = Generator::<Option<u32>, _>::new(|handle, ()| async move {
+    ///     let mut heartbeats = 0;
+    ///     for _ in 0..50 {
+    ///         std::thread::sleep(Duration::from_millis(1));
+    ///         if handle.yield_every(Duration::from_millis(10)).await.is_some() {
+    ///             heartbeats += 1;
+    ///         }
+    ///     }
+    ///     handle.yield_(Some(heartbeats)).await;
+    /// });
+    ///
+    /// let (values, ()) = generator.collect_with_final::<Vec<_>>();
+    /// let heartbeats = values.iter().filter(|value| value.is_none()).count() as u32;
+    /// assert!(heartbeats > 0);
+    /// assert_eq!(values.last(), Some(&Some(heartbeats)));
+    /// ```
+    pub async fn yield_every(&self, interval: Duration) -> Option<R> {
+        self.assert_attached();
+        let now = Instant::now();
+        let due = self.consume_fuel()
+            || match self.shared.heartbeat_since.get() {
+                Some(since) => now.duration_since(since) > interval,
+                None => {
+                    self.shared.heartbeat_since.set(Some(now));
+                    false
+                }
+            };
+        if due {
+            Some(self.yield_(Y::default()).await)
+        } else {
+            None
+        }
+    }
+
+    // Consumes one unit of fuel, returns `true` if there was none left
+    fn consume_fuel(&self) -> bool {
+        match self.shared.fuel.get() {
+            Some(0) => true,
+            Some(fuel) => {
+                self.shared.fuel.set(Some(fuel - 1));
+                false
+            }
+            None => false,
         }
     }
 }
@@ -198,6 +1527,27 @@ impl<Y, R> YieldHandle<Y, R> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::{
+        future::poll_fn,
+        pin::{Pin, pin},
+        task::Context,
+    };
+
+    /// A future that is pending a number of times without going through [`YieldHandle::yield_`].
+    struct Spin(usize);
+
+    impl Future for Spin {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+            if self.0 == 0 {
+                Poll::Ready(())
+            } else {
+                self.0 -= 1;
+                Poll::Pending
+            }
+        }
+    }
 
     #[test]
     fn test_empty() {
@@ -291,7 +1641,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "`async fn` resumed after completion")]
+    #[should_panic(expected = "was resumed after completion")]
     fn test_resumed_after_completion() {
         let mut generator = Generator::new(|handle, ()| async move {
             handle.yield_(42i32).await;
@@ -304,4 +1654,944 @@ mod tests {
         assert_eq!(generator.resume(), State::Complete("Ok"));
         generator.resume(); // This panics
     }
+
+    #[test]
+    fn test_resume_with_budget() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            Spin(3).await;
+            handle.yield_(2).await;
+            "Bye"
+        });
+
+        assert_eq!(generator.resume_with_budget((), 1), Ok(State::Yield(1)));
+        assert_eq!(generator.resume_with_budget((), 2), Err(BudgetExceeded));
+        assert_eq!(generator.poll_with_budget(1), Err(BudgetExceeded));
+        assert_eq!(generator.poll_with_budget(2), Ok(State::Yield(2)));
+        assert_eq!(
+            generator.resume_with_budget((), 1),
+            Ok(State::Complete("Bye"))
+        );
+    }
+
+    #[test]
+    fn test_resume_with_timeout() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            Spin(3).await;
+            handle.yield_(2).await;
+            std::future::pending::<()>().await;
+        });
+
+        let timeout = Duration::from_millis(5);
+        assert_eq!(
+            generator.resume_with_timeout((), timeout),
+            Ok(State::Yield(1))
+        );
+        assert_eq!(
+            generator.resume_with_timeout((), timeout),
+            Ok(State::Yield(2))
+        );
+
+        let start = Instant::now();
+        assert_eq!(generator.resume_with_timeout((), timeout), Err(TimedOut));
+        assert!(start.elapsed() >= timeout);
+        assert_eq!(generator.poll_with_timeout(Duration::ZERO), Err(TimedOut));
+    }
+
+    #[test]
+    fn test_resume_for() {
+        let mut co = Coroutine::new(|handle, mut value: u32| async move {
+            loop {
+                for _ in 0..5 {
+                    std::thread::sleep(Duration::from_millis(1));
+                    handle.checkpoint().await;
+                }
+                value = handle.yield_(value * 2).await;
+            }
+        });
+
+        let mut started = 0;
+        let mut slices = 0;
+        let state = loop {
+            slices += 1;
+            let state = co.resume_for(Duration::from_millis(2), || {
+                started += 1;
+                21
+            });
+            if let Ok(state) = state {
+                break state;
+            }
+        };
+        assert_eq!(state, State::Yield(42));
+        assert_eq!(started, 1);
+        assert!(slices > 1);
+
+        assert_eq!(
+            co.resume_for(Duration::from_secs(10), || 5),
+            Ok(State::Yield(10))
+        );
+    }
+
+    #[test]
+    fn test_maybe_yield_interval() {
+        let mut co = Coroutine::builder()
+            .yield_interval(3)
+            .build(|handle, ()| async move {
+                let mut received = Vec::new();
+                for _ in 0..7 {
+                    received.extend(handle.maybe_yield().await);
+                }
+                handle.yield_(1).await;
+                for _ in 0..2 {
+                    received.extend(handle.maybe_yield().await);
+                }
+                received
+            });
+
+        assert_eq!(co.resume_with(()), State::Yield(0));
+        assert_eq!(co.resume_with(()), State::Yield(0));
+        assert_eq!(co.resume_with(()), State::Yield(1));
+        assert_eq!(co.resume_with(()), State::Complete(vec![(), ()]));
+    }
+
+    #[test]
+    fn test_maybe_yield_deadline() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            loop {
+                std::thread::sleep(Duration::from_millis(1));
+                if handle.maybe_yield().await.is_some() {
+                    break;
+                }
+            }
+            handle.yield_(1).await;
+        });
+
+        assert_eq!(
+            generator.resume_for(Duration::from_millis(3), || ()),
+            Ok(State::Yield(0))
+        );
+        assert_eq!(generator.resume(), State::Yield(1));
+    }
+
+    #[test]
+    fn test_yield_every() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            let interval = Duration::from_millis(5);
+            assert_eq!(handle.yield_every(interval).await, None);
+            assert_eq!(handle.yield_every(interval).await, None);
+            std::thread::sleep(interval * 2);
+            assert_eq!(handle.yield_every(interval).await, Some(()));
+
+            // The heartbeat resets the clock
+            assert_eq!(handle.yield_every(interval).await, None);
+            std::thread::sleep(interval * 2);
+            handle.yield_(1).await;
+            assert_eq!(handle.yield_every(interval).await, None);
+        });
+
+        assert_eq!(generator.resume(), State::Yield(0));
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Complete(()));
+    }
+
+    #[test]
+    fn test_resume_with_fuel() {
+        let mut co = Coroutine::new(|handle, ()| async move {
+            let mut progress = 0;
+            while progress < 10 {
+                progress += 1;
+                if handle.maybe_yield().await.is_some() {
+                    handle.yield_(progress).await;
+                }
+            }
+            for _ in 0..2 {
+                handle.yield_every(Duration::from_secs(60)).await;
+            }
+            progress
+        });
+
+        assert_eq!(co.resume_with_fuel((), 3), State::Yield(0));
+        assert_eq!(co.resume_with(()), State::Yield(4));
+        assert_eq!(co.resume_with_fuel((), 0), State::Yield(0));
+        assert_eq!(co.resume_with(()), State::Yield(5));
+        assert_eq!(co.resume_with_fuel((), 5), State::Yield(0));
+        assert_eq!(co.resume_with_fuel((), 100), State::Complete(10));
+    }
+
+    #[test]
+    fn test_context() {
+        let mut co = Coroutine::builder()
+            .name("script")
+            .context(vec![10, 20, 30])
+            .build(|handle, index: usize| async move {
+                let mut index = index;
+                loop {
+                    index = handle.yield_(handle.context::<Vec<i32>>()[index]).await;
+                }
+            });
+
+        assert_eq!(co.resume_with(1), State::<_, ()>::Yield(20));
+        assert_eq!(co.resume_with(2), State::Yield(30));
+    }
+
+    #[test]
+    #[should_panic(expected = "the context of coroutine `script`")]
+    fn test_context_wrong_type() {
+        let mut generator = Generator::<(), ()>::builder()
+            .name("script")
+            .context(1u8)
+            .build(|handle, ()| async move {
+                handle.context::<u32>();
+            });
+        generator.resume();
+    }
+
+    #[test]
+    #[should_panic(expected = "was created without a context")]
+    fn test_context_missing() {
+        let mut generator = Generator::<(), ()>::new(|handle, ()| async move {
+            handle.context::<u32>();
+        });
+        generator.resume();
+    }
+
+    #[test]
+    #[should_panic(expected = "previous resume is still in progress")]
+    fn test_resume_after_budget_exceeded() {
+        let mut generator = Generator::<(), _>::new(|_handle, ()| async move {
+            Spin(1).await;
+        });
+
+        assert_eq!(generator.resume_with_budget((), 1), Err(BudgetExceeded));
+        generator.resume(); // This panics
+    }
+
+    #[test]
+    #[should_panic(expected = "polled 10 times without yielding or completing")]
+    fn test_spin_policy_panic() {
+        let mut generator = Generator::<(), _>::new(|_handle, ()| async move {
+            Spin(usize::MAX).await;
+        })
+        .with_spin_policy(SpinPolicy::Panic(10));
+
+        generator.resume(); // This panics
+    }
+
+    #[test]
+    fn test_spin_policy_checkpoints() {
+        let mut co = Coroutine::<(), _, _>::new(|handle, count: usize| async move {
+            for _ in 0..count {
+                handle.checkpoint().await;
+            }
+            count
+        });
+
+        assert_eq!(co.resume_with(1_000_001), State::Complete(1_000_001));
+    }
+
+    #[test]
+    #[should_panic(expected = "polled 10 times without yielding or completing")]
+    fn test_spin_policy_foreign_pending_after_checkpoints() {
+        let mut generator = Generator::<(), _>::new(|handle, ()| async move {
+            for _ in 0..20 {
+                handle.checkpoint().await;
+            }
+            Spin(usize::MAX).await;
+        })
+        .with_spin_policy(SpinPolicy::Panic(10));
+
+        generator.resume(); // This panics
+    }
+
+    #[test]
+    fn test_spin_policy_spin() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            Spin(100).await;
+            handle.yield_(1).await;
+        })
+        .with_spin_policy(SpinPolicy::Spin);
+
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Complete(()));
+    }
+
+    #[test]
+    fn test_builder() {
+        let yields = Rc::new(RefCell::new(Vec::new()));
+        let mut generator = Generator::builder()
+            .name("counter")
+            .spin_policy(SpinPolicy::Spin)
+            .on_yield({
+                let yields = Rc::clone(&yields);
+                move |value: &i32| yields.borrow_mut().push(*value)
+            })
+            .build(|handle, ()| async move {
+                handle.yield_(1).await;
+                handle.yield_(2).await;
+                "Bye"
+            });
+
+        assert_eq!(generator.name(), Some("counter"));
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(2));
+        assert_eq!(generator.resume(), State::Complete("Bye"));
+        assert_eq!(*yields.borrow(), [1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple values were yielded without awaiting them")]
+    fn test_yield_policy_panic() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            yield_first_then(&handle, 1, 2).await;
+        });
+
+        generator.resume(); // This panics
+    }
+
+    #[test]
+    fn test_yield_policy_overwrite() {
+        let mut generator = Generator::builder()
+            .yield_policy(YieldPolicy::Overwrite)
+            .build(|handle, ()| async move {
+                yield_first_then(&handle, 1, 2).await;
+                handle.yield_(3).await;
+            });
+
+        assert_eq!(generator.resume(), State::Yield(2));
+        assert_eq!(generator.resume(), State::Yield(3));
+        assert_eq!(generator.resume(), State::Complete(()));
+    }
+
+    #[test]
+    fn test_yield_policy_queue() {
+        let mut co = Coroutine::builder().yield_policy(YieldPolicy::Queue).build(
+            |handle, _initial| async move {
+                let mut first = pin!(handle.yield_(1));
+                let mut second = pin!(handle.yield_(2));
+                let (mut a, mut b) = (None, None);
+                poll_fn(|cx| {
+                    if a.is_none() {
+                        a = poll_ready(first.as_mut(), cx);
+                    }
+                    if b.is_none() {
+                        b = poll_ready(second.as_mut(), cx);
+                    }
+                    match (a, b) {
+                        (Some(a), Some(b)) => Poll::Ready(a + b),
+                        _ => Poll::Pending,
+                    }
+                })
+                .await
+            },
+        );
+
+        assert_eq!(co.resume_with(0), State::Yield(1));
+        assert_eq!(co.resume_with(10), State::Yield(2));
+        assert_eq!(co.resume_with(20), State::Complete(30));
+    }
+
+    /// Polls a yield future once and drops it, then yields another value.
+    async fn yield_first_then<Y>(handle: &YieldHandle<Y>, first: Y, then: Y) {
+        let mut first = pin!(handle.yield_(first));
+        poll_fn(|cx| {
+            assert!(poll_ready(first.as_mut(), cx).is_none());
+            Poll::Ready(())
+        })
+        .await;
+        handle.yield_(then).await;
+    }
+
+    fn poll_ready<F: Future>(future: Pin<&mut F>, cx: &mut Context<'_>) -> Option<F::Output> {
+        match future.poll(cx) {
+            Poll::Ready(output) => Some(output),
+            Poll::Pending => None,
+        }
+    }
+
+    #[test]
+    fn test_push() {
+        let mut co = Coroutine::new(|handle, mut sum| async move {
+            for value in [1, 2, 3] {
+                handle.push(value);
+            }
+            sum += handle.yield_(4).await;
+            handle.push(5);
+            sum += handle.yield_(6).await;
+            sum
+        });
+
+        assert_eq!(co.resume_with(0), State::Yield(1));
+        assert_eq!(co.resume_with(10), State::Yield(2));
+        assert_eq!(co.resume_with(20), State::Yield(3));
+        assert_eq!(co.resume_with(30), State::Yield(4));
+        assert_eq!(co.resume_with(40), State::Yield(5));
+        assert_eq!(co.resume_with(50), State::Yield(6));
+        assert_eq!(co.resume_with(60), State::Complete(100));
+    }
+
+    #[test]
+    fn test_resume_batch() {
+        let mut co = Coroutine::new(|handle, mut sum| async move {
+            for value in 0..sum {
+                handle.push(value);
+            }
+            sum += handle.yield_(sum).await;
+            handle.push(sum);
+            sum
+        });
+
+        assert_eq!(co.resume_batch(3), (vec![0, 1, 2, 3], None));
+        assert_eq!(co.resume_batch(4), (vec![7], Some(7)));
+    }
+
+    #[test]
+    fn test_yield_many() {
+        let mut co = Coroutine::new(|handle, n| async move {
+            let n = handle.yield_many(0..n).await.unwrap();
+            assert_eq!(handle.yield_many(0..0).await, None);
+            handle.yield_many(0..n).await
+        });
+
+        assert_eq!(co.resume_batch(3), (vec![0, 1, 2], None));
+        assert_eq!(co.resume_with(2), State::Yield(0));
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert_eq!(co.resume_with(5), State::Complete(Some(5)));
+    }
+
+    #[test]
+    fn test_observer() {
+        struct Events(Rc<RefCell<Vec<String>>>);
+
+        impl Observer<i32, &'static str, i32> for Events {
+            fn on_start(&mut self) {
+                self.0.borrow_mut().push("start".into());
+            }
+
+            fn on_resume(&mut self, resume: &i32) {
+                self.0.borrow_mut().push(format!("resume {resume}"));
+            }
+
+            fn on_yield(&mut self, value: &i32) {
+                self.0.borrow_mut().push(format!("yield {value}"));
+            }
+
+            fn on_complete(&mut self, value: &&'static str) {
+                self.0.borrow_mut().push(format!("complete {value}"));
+            }
+
+            fn on_drop_suspended(&mut self) {
+                self.0.borrow_mut().push("drop suspended".into());
+            }
+        }
+
+        let new = |events: &Rc<RefCell<Vec<String>>>| {
+            Coroutine::builder()
+                .observer(Events(Rc::clone(events)))
+                .build(|handle, value| async move {
+                    handle.yield_(value * 2).await;
+                    "Bye"
+                })
+        };
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut co = new(&events);
+        assert_eq!(co.resume_with(1), State::Yield(2));
+        assert_eq!(co.resume_with(2), State::Complete("Bye"));
+        drop(co);
+        assert_eq!(
+            *events.borrow(),
+            ["start", "resume 1", "yield 2", "resume 2", "complete Bye"]
+        );
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        drop(new(&events));
+        assert!(events.borrow().is_empty());
+
+        let mut co = new(&events);
+        assert_eq!(co.resume_with(3), State::Yield(6));
+        drop(co);
+        assert_eq!(
+            *events.borrow(),
+            ["start", "resume 3", "yield 6", "drop suspended"]
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.push(1);
+            handle.yield_(2).await;
+            Spin(3).await;
+            "Bye"
+        });
+
+        assert_eq!(generator.stats(), Stats::default());
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(2));
+        assert_eq!(generator.resume(), State::Complete("Bye"));
+
+        let stats = generator.stats();
+        assert_eq!(stats.resumes, 3);
+        assert_eq!(stats.polls, 5);
+        assert_eq!(stats.yields, 2);
+    }
+
+    #[test]
+    fn test_poisoned() {
+        let mut co = Coroutine::restartable(|handle, value: i32| async move {
+            assert!(value >= 0, "negative value");
+            handle.yield_(value).await;
+        });
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| co.resume_with(-1)));
+        assert!(panic.is_err());
+        assert!(co.is_poisoned());
+        assert_eq!(POLLING.get(), None);
+        assert!(!co.yield_handle.shared.polling.get());
+
+        let message = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| co.resume_with(1)))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.ends_with("was resumed after its body panicked"));
+
+        co.reset();
+        assert!(!co.is_poisoned());
+        assert_eq!(co.resume_with(1), State::Yield(1));
+    }
+
+    #[test]
+    fn test_panic_message_origin() {
+        let mut generator = Generator::builder()
+            .name("script")
+            .build(|_handle: YieldHandle<()>, ()| async {});
+        assert_eq!(generator.name(), Some("script"));
+        assert_eq!(generator.location().file(), file!());
+
+        assert_eq!(generator.resume(), State::Complete(()));
+        let message = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| generator.resume()))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            *message,
+            format!(
+                "coroutine `script` (created at {}) was resumed after completion",
+                generator.location()
+            )
+        );
+    }
+
+    #[test]
+    fn test_resumable() {
+        fn drive<C: Resumable<i32>>(
+            resumable: &mut C,
+            resumes: &[i32],
+        ) -> Vec<State<C::Yield, C::Complete>> {
+            resumes
+                .iter()
+                .map(|resume| resumable.resume_with(*resume))
+                .collect()
+        }
+
+        let mut co = Coroutine::new(|handle, value| async move {
+            let value = handle.yield_(value).await;
+            let value = handle.yield_(value).await;
+            value * 2
+        });
+        assert_eq!(
+            drive(&mut co, &[1, 2, 3]),
+            [State::Yield(1), State::Yield(2), State::Complete(6)]
+        );
+
+        let mut co = Coroutine::new(|handle, value| async move {
+            let value = handle.yield_(value).await;
+            let value = handle.yield_(value).await;
+            value * 2
+        })
+        .dedup();
+        assert_eq!(
+            drive(&mut co, &[1, 1]),
+            [State::Yield(1), State::Complete(2)]
+        );
+    }
+
+    #[test]
+    fn test_boxed() {
+        let mut coroutines: Vec<BoxCoroutine<i32, &str, ()>> = vec![
+            Generator::new(|handle, ()| async move {
+                handle.yield_(1).await;
+                "a"
+            })
+            .boxed(),
+            Generator::new(|handle, ()| async move {
+                handle.yield_(2).await;
+                handle.yield_(2).await;
+                "b"
+            })
+            .dedup()
+            .boxed(),
+        ];
+
+        for co in &mut coroutines {
+            assert!(co.resume_with(()).is_yield());
+        }
+        for co in &mut coroutines {
+            assert!(co.resume_with(()).is_complete());
+        }
+    }
+
+    #[test]
+    fn test_unresume() {
+        // Splits the input into numbers, putting back the character that ends a number
+        let mut co = Consumer::new(|handle, first: char| async move {
+            handle.unresume(first);
+            let mut numbers = Vec::new();
+            loop {
+                let mut number = String::new();
+                let mut c = handle.recv().await;
+                while c.is_ascii_digit() {
+                    number.push(c);
+                    c = handle.recv().await;
+                }
+                if !number.is_empty() {
+                    numbers.push(number);
+                    handle.unresume(c);
+                } else if c == '.' {
+                    break numbers;
+                }
+            }
+        });
+
+        let (_, numbers) = co.drive_with("12 3,,45.".chars());
+        assert_eq!(numbers.unwrap(), ["12", "3", "45"]);
+    }
+
+    #[test]
+    fn test_peek_resume() {
+        // Sums the input, but multiplies if the next value is negative
+        let mut co = Consumer::new(|handle, first: i32| async move {
+            handle.unresume(first);
+            let mut result = handle.recv().await;
+            loop {
+                if *handle.peek_resume().await == 0 {
+                    break result;
+                }
+                if *handle.peek_resume().await < 0 {
+                    result *= -handle.recv().await;
+                } else {
+                    result += handle.recv().await;
+                }
+            }
+        });
+
+        assert_eq!(co.drive_with([1, 2, -3, 4, 0]).1, Some(13));
+    }
+
+    #[test]
+    fn test_resume_default() {
+        let mut co = Coroutine::new(|handle, mut total: u32| async move {
+            total += handle.yield_(total).await;
+            total + 1
+        });
+
+        assert_eq!(co.resume(), State::Yield(0));
+        assert_eq!(co.resume(), State::Complete(1));
+    }
+
+    #[test]
+    fn test_state_combinators() {
+        let yielded = || State::<i32, &str>::Yield(1);
+        let completed = || State::<i32, &str>::Complete("done");
+
+        assert_eq!(yielded().map_yield(|v| v * 2), State::Yield(2));
+        assert_eq!(completed().map_yield(|v| v * 2), State::Complete("done"));
+        assert_eq!(completed().map_complete(str::len), State::Complete(4));
+        assert_eq!(yielded().into_yield(), Some(1));
+        assert_eq!(yielded().into_complete(), None);
+        assert_eq!(completed().into_complete(), Some("done"));
+        assert_eq!(yielded().unwrap_yield(), 1);
+        assert_eq!(completed().expect_complete("not complete"), "done");
+        assert_eq!(completed().yield_or_else(|v| v.len() as i32), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "called `State::unwrap_yield()` on a `Complete` state")]
+    fn test_state_unwrap_yield() {
+        State::<(), ()>::Complete(()).unwrap_yield();
+    }
+
+    #[test]
+    fn test_state_traits() {
+        let mut state = State::<i32, i32>::Yield(1);
+        *state.as_yield_mut().unwrap() += 1;
+        assert_eq!(state.as_complete_mut(), None);
+
+        let states = [state, State::Complete(0), state, State::Yield(0)];
+        let unique = states.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), 3);
+        assert_eq!(states.iter().max(), Some(&State::Complete(0)));
+    }
+
+    #[test]
+    fn test_state_conversions() {
+        use std::ops::ControlFlow;
+
+        assert_eq!(State::<i32, &str>::Yield(1).into_result(), Err(1));
+        assert_eq!(State::<i32, &str>::Complete("Bye").into_result(), Ok("Bye"));
+
+        let flow = ControlFlow::<&str, i32>::from(State::Yield(1));
+        assert_eq!(flow, ControlFlow::Continue(1));
+        assert_eq!(State::from(flow), State::Yield(1));
+        let flow = ControlFlow::<&str, i32>::from(State::Complete("Bye"));
+        assert_eq!(flow, ControlFlow::Break("Bye"));
+        assert_eq!(State::from(flow), State::Complete("Bye"));
+    }
+
+    #[cfg(feature = "either")]
+    #[test]
+    fn test_state_either() {
+        use either::Either;
+
+        let either = Either::<i32, &str>::from(State::Yield(1));
+        assert_eq!(either, Either::Left(1));
+        assert_eq!(State::from(either), State::Yield(1));
+        assert_eq!(
+            State::from(Either::<i32, _>::Right("Bye")),
+            State::Complete("Bye")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_state_serde() {
+        let json = serde_json::to_string(&State::<i32, &str>::Yield(1)).unwrap();
+        assert_eq!(json, r#"{"Yield":1}"#);
+        let state = serde_json::from_str::<State<i32, String>>(r#"{"Complete":"Bye"}"#).unwrap();
+        assert_eq!(state, State::Complete("Bye".to_string()));
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut co = Coroutine::builder()
+            .name("debug")
+            .build(|handle, ()| async move {
+                assert_eq!(
+                    format!("{handle:?}"),
+                    r#"YieldHandle { name: Some("debug"), pending_value: false, pending_resume: false, .. }"#
+                );
+                handle.yield_(1).await;
+            });
+
+        let debug = |co: &Coroutine<'_, i32, (), ()>| format!("{co:?}");
+        assert_eq!(
+            debug(&co),
+            r#"Coroutine { name: Some("debug"), status: "created", resumes: 0, .. }"#
+        );
+        co.resume();
+        assert_eq!(
+            debug(&co),
+            r#"Coroutine { name: Some("debug"), status: "suspended", resumes: 1, .. }"#
+        );
+        co.resume();
+        assert_eq!(
+            debug(&co),
+            r#"Coroutine { name: Some("debug"), status: "completed", resumes: 2, .. }"#
+        );
+    }
+
+    #[test]
+    fn test_into_completed() {
+        let co = Coroutine::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            handle.push(2);
+            handle.push(3);
+            "Bye"
+        });
+
+        let IntoCompleted::Pending(mut co) = co.into_completed() else {
+            panic!("the coroutine has not completed");
+        };
+        assert_eq!(co.resume(), State::Yield(1));
+        let IntoCompleted::Pending(mut co) = co.into_completed() else {
+            panic!("the coroutine has not completed");
+        };
+        assert_eq!(co.resume(), State::Yield(2));
+        assert!(matches!(
+            co.into_completed(),
+            IntoCompleted::Completed("Bye")
+        ));
+    }
+
+    #[test]
+    fn test_into_completed_terminated() {
+        let mut co = Coroutine::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            "Bye"
+        });
+
+        assert_eq!(co.resume(), State::Yield(1));
+        assert_eq!(co.resume(), State::Complete("Bye"));
+        assert!(matches!(co.into_completed(), IntoCompleted::Terminated));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut co = Coroutine::restartable(|handle, start| async move {
+            let mut total = start;
+            while total < 10 {
+                total += handle.yield_(total).await;
+            }
+            total
+        });
+        assert!(co.is_restartable());
+
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert_eq!(co.resume_with(2), State::Yield(3));
+        co.reset();
+        assert_eq!(co.resume_with(5), State::Yield(5));
+        assert_eq!(co.resume_with(5), State::Complete(10));
+        co.reset();
+        assert_eq!(co.resume_with(20), State::Complete(20));
+        assert_eq!(co.stats().resumes, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not restartable")]
+    fn test_reset_not_restartable() {
+        let mut co = Coroutine::new(|handle, ()| async move { handle.yield_(1).await });
+        assert!(!co.is_restartable());
+        co.reset();
+    }
+
+    #[test]
+    fn test_prime() {
+        let (mut generator, first) = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            handle.yield_(2).await;
+        })
+        .prime(())
+        .unwrap();
+        assert_eq!(first, 1);
+        assert_eq!(generator.stats().resumes, 1);
+        assert_eq!(generator.resume(), State::Yield(2));
+
+        let completed = Generator::<(), _>::new(|_handle, ()| async { "Bye" }).prime(());
+        assert_eq!(completed.err(), Some("Bye"));
+    }
+
+    #[test]
+    fn test_borrowed_completion() {
+        // Generic code driving a coroutine does not need `T: 'static`
+        fn finish<Y, T>(generator: &mut Generator<'_, Y, T>) -> T {
+            loop {
+                if let State::Complete(value) = generator.resume() {
+                    break value;
+                }
+            }
+        }
+
+        let text = String::from("first second third");
+        let mut words = Generator::new(|handle, ()| {
+            let text = text.as_str();
+            async move {
+                let mut words = text.split(' ');
+                let first = words.next().unwrap();
+                for word in words {
+                    handle.yield_(word).await;
+                }
+                first
+            }
+        });
+
+        assert_eq!(words.resume(), State::Yield("second"));
+        let first: &str = finish(&mut words);
+        assert_eq!(first, &text[..5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the yield handle was used outside of a resume of its coroutine")]
+    fn test_detached_handle() {
+        let escaped = Rc::new(RefCell::new(None));
+        let mut generator = Generator::new({
+            let escaped = Rc::clone(&escaped);
+            move |handle, ()| async move {
+                *escaped.borrow_mut() = Some(handle);
+                std::future::pending::<()>().await;
+            }
+        });
+        generator.resume_with_budget((), 1).unwrap_err();
+
+        let handle = escaped.borrow_mut().take().unwrap();
+        handle.push(1); // This panics
+    }
+
+    #[test]
+    #[should_panic(expected = "the yield handle was used while another coroutine is being resumed")]
+    fn test_smuggled_handle() {
+        let mut outer = Generator::new(|handle, ()| async move {
+            let mut inner = Generator::<i32, ()>::new(move |_, ()| async move {
+                handle.yield_(1).await;
+            });
+            inner.resume(); // This panics
+        });
+        outer.resume();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "was dropped while its yield handle is still alive")]
+    fn test_leaked_handle() {
+        let escaped = Rc::new(RefCell::new(None));
+        let mut generator = Coroutine::<(), (), ()>::builder().name("leaky").build({
+            let escaped = Rc::clone(&escaped);
+            move |handle, ()| async move {
+                *escaped.borrow_mut() = Some(handle);
+            }
+        });
+        generator.resume();
+        drop(generator); // This panics
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_coroutine_macro() {
+        #[crate::coroutine]
+        async fn accumulate(handle: YieldHandle<i32, i32>, mut total: i32) -> i32 {
+            while total < 10 {
+                total += handle.yield_(total).await;
+            }
+            total
+        }
+
+        let mut co = accumulate(1);
+        assert_eq!(co.resume_with(100), State::Yield(1));
+        assert_eq!(co.resume_with(5), State::Yield(6));
+        assert_eq!(co.resume_with(5), State::Complete(11));
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_yield_types() {
+        crate::yield_types! {
+            #[derive(Debug, PartialEq)]
+            enum Event {
+                Progress(f32),
+                LogLine(String),
+            }
+        }
+
+        let mut co = Coroutine::<Event, i32, i32>::new(|handle, _| async move {
+            handle.yield_progress(0.5).await;
+            handle.yield_log_line("done".to_string()).await
+        });
+
+        assert_eq!(co.resume_with(1), State::Yield(Event::Progress(0.5)));
+        assert_eq!(
+            co.resume_with(2),
+            State::Yield(Event::LogLine("done".to_string()))
+        );
+        assert_eq!(co.resume_with(3), State::Complete(3));
+    }
 }
@@ -0,0 +1,247 @@
+// Declarative macros that create coroutines without spelling out the handle
+
+/// Creates a [`Generator`](crate::Generator) from a block, in which `yield_!(value)` yields a
+/// value.
+///
+/// The block is the body of the generator, so it can `.await` and its value is the completion
+/// value. Variables from the surrounding scope are moved into it.
+///
+/// ```
+/// use async_coroutine::{Generator, generator};
+///
+/// async fn compute() -> u32 {
+///     3
+/// }
+///
+/// let generator: Generator<u32, &str> = generator! {
+///     yield_!(1);
+///     yield_!(2);
+///     yield_!(compute().await);
+///     "done"
+/// };
+///
+/// assert_eq!(generator.collect_with_final::<Vec<_>>(), (vec![1, 2, 3], "done"));
+/// ```
+#[macro_export]
+macro_rules! generator {
+    // `$d` is a literal `$`, which can not be written in the nested macro directly
+    (@with_dollar ($d:tt) $($body:tt)*) => {
+        $crate::Generator::new(move |_handle, ()| async move {
+            #[allow(unused_macros)]
+            macro_rules! yield_ {
+                ($d value:expr $d(,)?) => {
+                    _handle.yield_($d value).await
+                };
+            }
+            $($body)*
+        })
+    };
+    ($($body:tt)*) => {
+        $crate::generator!(@with_dollar ($) $($body)*)
+    };
+}
+
+/// Creates a [`Consumer`](crate::Consumer) from a block, in which `recv_!()` receives the next
+/// resume value.
+///
+/// The first `recv_!()` returns the initial value, every further one suspends the consumer until
+/// it is resumed again. The value of the block is the completion value.
+///
+/// ```
+/// use async_coroutine::{Consumer, State, consumer};
+///
+/// let mut sum: Consumer<u32, u32> = consumer! {
+///     let mut sum = 0;
+///     loop {
+///         match recv_!() {
+///             0 => break sum,
+///             value => sum += value,
+///         }
+///     }
+/// };
+///
+/// assert_eq!(sum.resume_with(1), State::Yield(()));
+/// assert_eq!(sum.resume_with(2), State::Yield(()));
+/// assert_eq!(sum.resume_with(0), State::Complete(3));
+/// ```
+#[macro_export]
+macro_rules! consumer {
+    (@with_dollar ($d:tt) $($body:tt)*) => {
+        $crate::Coroutine::new(move |_handle: $crate::YieldHandle<(), _>, initial| async move {
+            #[allow(unused_mut, unused_variables)]
+            let mut initial = ::std::option::Option::Some(initial);
+            #[allow(unused_macros)]
+            macro_rules! recv_ {
+                () => {
+                    match initial.take() {
+                        ::std::option::Option::Some(value) => value,
+                        ::std::option::Option::None => _handle.recv().await,
+                    }
+                };
+            }
+            $($body)*
+        })
+    };
+    ($($body:tt)*) => {
+        $crate::consumer!(@with_dollar ($) $($body)*)
+    };
+}
+
+/// Creates a [`Coroutine`](crate::Coroutine) that processes inputs into outputs from a block, in
+/// which `recv_!()` receives the current input and `emit_!(value)` yields an output.
+///
+/// The initial value is the first input, every `emit_!` suspends the coroutine and makes the value
+/// it is resumed with the next input. Inputs that are not received before the next `emit_!` are
+/// dropped. The value of the block is the completion value.
+///
+/// ```
+/// use async_coroutine::{Coroutine, State, processor};
+///
+/// let mut doubler: Coroutine<u32, (), u32> = processor! {
+///     loop {
+///         let input = recv_!();
+///         emit_!(input * 2);
+///     }
+/// };
+///
+/// assert_eq!(doubler.resume_with(1), State::Yield(2));
+/// assert_eq!(doubler.resume_with(5), State::Yield(10));
+/// ```
+///
+/// # Panics
+///
+/// `recv_!()` panics if the current input has already been received.
+#[macro_export]
+macro_rules! processor {
+    (@with_dollar ($d:tt) $($body:tt)*) => {
+        $crate::Coroutine::new(move |_handle, initial| async move {
+            #[allow(unused_mut, unused_variables)]
+            let mut input = ::std::option::Option::Some(initial);
+            #[allow(unused_macros)]
+            macro_rules! recv_ {
+                () => {
+                    input
+                        .take()
+                        .expect("`recv_!` was called twice without `emit_!` in between")
+                };
+            }
+            #[allow(unused_macros)]
+            macro_rules! emit_ {
+                ($d value:expr $d(,)?) => {
+                    input = ::std::option::Option::Some(_handle.yield_($d value).await)
+                };
+            }
+            $($body)*
+        })
+    };
+    ($($body:tt)*) => {
+        $crate::processor!(@with_dollar ($) $($body)*)
+    };
+}
+
+/// Creates a [`StackCoroutine`](crate::stack::StackCoroutine) whose body is pinned in the stack
+/// frame of the caller, from a function that takes the
+/// [`StackHandle`](crate::stack::StackHandle) and the initial value.
+///
+/// Like [`std::pin::pin!`], the macro has to be used as the initializer of a `let` statement to
+/// keep the body alive. It does not allocate, and the body may borrow from the enclosing scope.
+///
+/// ```
+/// use async_coroutine::{State, stack_coroutine};
+///
+/// let limit = 2;
+/// let mut generator = stack_coroutine!(|handle, ()| async move {
+///     for value in 0..limit {
+///         handle.yield_(value).await;
+///     }
+/// });
+///
+/// assert_eq!(generator.resume(), State::Yield(0));
+/// assert_eq!(generator.resume(), State::Yield(1));
+/// assert_eq!(generator.resume(), State::Complete(()));
+/// ```
+#[macro_export]
+macro_rules! stack_coroutine {
+    ($f:expr $(,)?) => {
+        $crate::stack::StackCoroutine {
+            slot: &$crate::stack::Slot::new(),
+            init: $crate::stack::init($f),
+            body: ::std::pin::pin!(::std::option::Option::None),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Consumer, Coroutine, Generator, State};
+
+    #[test]
+    fn test_generator() {
+        let handle = 10;
+        let mut generator: Generator<'_, i32, i32> = generator! {
+            for value in 1..=2 {
+                yield_!(value);
+            }
+            handle
+        };
+
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(2));
+        assert_eq!(generator.resume(), State::Complete(10));
+    }
+
+    #[test]
+    fn test_consumer() {
+        let mut consumer: Consumer<'_, Vec<char>, char> = consumer! {
+            let mut received = Vec::new();
+            loop {
+                match recv_!() {
+                    '.' => break received,
+                    value => received.push(value),
+                }
+            }
+        };
+
+        assert_eq!(consumer.feed_all(['a', 'b', '.']), Some(vec!['a', 'b']));
+    }
+
+    #[test]
+    fn test_processor() {
+        let mut co: Coroutine<'_, i32, i32, i32> = processor! {
+            let mut total = 0;
+            loop {
+                let input = recv_!();
+                if input == 0 {
+                    break total;
+                }
+                total += input;
+                emit_!(total);
+            }
+        };
+
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert_eq!(co.resume_with(2), State::Yield(3));
+        assert_eq!(co.resume_with(0), State::Complete(3));
+    }
+
+    #[test]
+    fn test_unused_macros() {
+        let generator: Generator<'_, (), u8> = generator! { 1 };
+        let consumer: Consumer<'_, u8, ()> = consumer! { 2 };
+        let processor: Coroutine<'_, (), u8, ()> = processor! { 3 };
+
+        assert_eq!(generator.collect_with_final::<Vec<_>>().1, 1);
+        assert_eq!(consumer.run((), |()| ()), 2);
+        assert_eq!(processor.run((), |()| ()), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "`recv_!` was called twice without `emit_!` in between")]
+    fn test_processor_recv_twice() {
+        let mut co: Coroutine<'_, (), (), ()> = processor! {
+            recv_!();
+            recv_!();
+        };
+        co.resume();
+    }
+}
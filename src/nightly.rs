@@ -0,0 +1,167 @@
+// Interop with the unstable `std::ops::Coroutine` and `AsyncIterator` traits, enabled by the
+// `nightly` feature
+
+use crate::{Coroutine, Generator, State};
+use std::{
+    async_iter::AsyncIterator,
+    ops::{self, CoroutineState},
+    pin::{Pin, pin},
+    task::{Context, Poll},
+};
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R>
+where
+    Y: 'a,
+    R: 'a,
+{
+    /// Wraps a language coroutine, which uses real `yield` expressions, into a [`Coroutine`].
+    ///
+    /// The initial value is passed to the first resume of the language coroutine, every later
+    /// resume value to the `yield` expression it is suspended at.
+    #[track_caller]
+    pub fn from_std<G>(coroutine: G) -> Self
+    where
+        G: ops::Coroutine<R, Yield = Y, Return = T> + 'a,
+    {
+        Self::new(|handle, initial| async move {
+            let mut coroutine = pin!(coroutine);
+            let mut resume = initial;
+            loop {
+                match coroutine.as_mut().resume(resume) {
+                    CoroutineState::Yielded(value) => resume = handle.yield_(value).await,
+                    CoroutineState::Complete(value) => break value,
+                }
+            }
+        })
+    }
+}
+
+impl<Y, T, R> ops::Coroutine<R> for Coroutine<'_, Y, T, R> {
+    type Yield = Y;
+    type Return = T;
+
+    fn resume(self: Pin<&mut Self>, arg: R) -> CoroutineState<Y, T> {
+        match self.get_mut().resume_with(arg) {
+            State::Yield(value) => CoroutineState::Yielded(value),
+            State::Complete(value) => CoroutineState::Complete(value),
+        }
+    }
+}
+
+/// The body is polled once per call with the waker of `cx`, so futures it awaits wake the task
+/// that polls the iterator instead of being spun on. The completion value is dropped and the
+/// iterator keeps returning `None` afterwards.
+impl<Y, T> AsyncIterator for Generator<'_, Y, T> {
+    type Item = Y;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Y>> {
+        let this = self.get_mut();
+        if this.is_terminated() {
+            return Poll::Ready(None);
+        }
+
+        if !this.in_progress {
+            this.start_resume(());
+        }
+        match this.poll_step(Some(cx.waker())) {
+            Some(State::Yield(value)) => Poll::Ready(Some(value)),
+            Some(State::Complete(_)) => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Coroutine, Generator, State, yield_now::yield_now};
+    use std::{
+        async_iter::AsyncIterator,
+        ops::{self, CoroutineState},
+        pin::Pin,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    #[test]
+    fn test_from_std() {
+        let mut co = Coroutine::from_std(
+            #[coroutine]
+            |mut total: i32| {
+                while total < 10 {
+                    total += yield total;
+                }
+                total
+            },
+        );
+
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert_eq!(co.resume_with(4), State::Yield(5));
+        assert_eq!(co.resume_with(6), State::Complete(11));
+    }
+
+    #[test]
+    fn test_std_coroutine() {
+        let mut co = Coroutine::new(|handle, value| async move {
+            let value = handle.yield_(value * 2).await;
+            value + 1
+        });
+
+        let mut co = Pin::new(&mut co);
+        assert_eq!(
+            ops::Coroutine::resume(co.as_mut(), 1),
+            CoroutineState::Yielded(2)
+        );
+        assert_eq!(
+            ops::Coroutine::resume(co.as_mut(), 5),
+            CoroutineState::Complete(6)
+        );
+    }
+
+    #[test]
+    fn test_async_iterator() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+            handle.yield_(2).await;
+            "Bye"
+        });
+
+        let mut context = Context::from_waker(Waker::noop());
+        let mut next = || Pin::new(&mut generator).poll_next(&mut context);
+        assert_eq!(next(), Poll::Ready(Some(1)));
+        assert_eq!(next(), Poll::Ready(Some(2)));
+        assert_eq!(next(), Poll::Ready(None));
+        assert_eq!(next(), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_async_iterator_pending() {
+        struct CountingWaker(AtomicUsize);
+
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.wake_by_ref();
+            }
+
+            fn wake_by_ref(self: &Arc<Self>) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut generator = Generator::new(|handle, ()| async move {
+            yield_now().await;
+            handle.yield_(1).await;
+        });
+
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut context = Context::from_waker(&waker);
+        let mut next = || Pin::new(&mut generator).poll_next(&mut context);
+        assert_eq!(next(), Poll::Pending);
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+        assert_eq!(next(), Poll::Ready(Some(1)));
+        assert_eq!(next(), Poll::Ready(None));
+    }
+}
@@ -0,0 +1,27 @@
+/// An observer of the lifecycle events of a [`Coroutine`](crate::Coroutine), installed via
+/// [`CoroutineBuilder::observer`](crate::CoroutineBuilder::observer).
+///
+/// All methods do nothing by default, so implementors only need to override the events they are
+/// interested in.
+pub trait Observer<Y, T, R> {
+    /// Called when the coroutine is resumed for the first time, before [`Observer::on_resume`].
+    fn on_start(&mut self) {}
+
+    /// Called with every value the coroutine is resumed with, including the initial value.
+    fn on_resume(&mut self, resume: &R) {
+        let _ = resume;
+    }
+
+    /// Called with every value the coroutine yields.
+    fn on_yield(&mut self, value: &Y) {
+        let _ = value;
+    }
+
+    /// Called with the completion value of the coroutine.
+    fn on_complete(&mut self, value: &T) {
+        let _ = value;
+    }
+
+    /// Called if the coroutine is dropped after it was started but before it completed.
+    fn on_drop_suspended(&mut self) {}
+}
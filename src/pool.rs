@@ -0,0 +1,147 @@
+use crate::{Coroutine, Stats, YieldHandle};
+use std::{
+    cell::RefCell,
+    future::Future,
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
+
+/// A pool of coroutines created from the same function, which recycles finished coroutines
+/// instead of allocating new ones.
+///
+/// The coroutines handed out by [`CoroutinePool::get`] return to the pool when they are dropped.
+/// They are reset with [`Coroutine::reset`], which keeps the allocations of the coroutine, its
+/// internal channel and its body, so creating many short-lived coroutines does not allocate once
+/// the pool is warm.
+///
+/// ```
+/// use async_coroutine::{CoroutinePool, State};
+///
+/// let pool = CoroutinePool::new(|handle, count| async move {
+///     for value in 0..count {
+///         handle.yield_(value).await;
+///     }
+/// });
+///
+/// for _ in 0..3 {
+///     let mut generator = pool.get();
+///     assert_eq!(generator.resume_with(2), State::Yield(0));
+///     assert_eq!(generator.resume_with(0), State::Yield(1));
+///     assert_eq!(generator.resume_with(0), State::Complete(()));
+/// }
+/// assert_eq!(pool.idle(), 1);
+/// ```
+pub struct CoroutinePool<Y, T, R> {
+    create: Box<dyn Fn() -> Coroutine<'static, Y, T, R>>,
+    idle: Rc<RefCell<Vec<Coroutine<'static, Y, T, R>>>>,
+}
+
+impl<Y, T, R> CoroutinePool<Y, T, R> {
+    /// Creates an empty pool of coroutines that run the given function, see [`Coroutine::new`].
+    pub fn new<F>(f: impl Fn(YieldHandle<Y, R>, R) -> F + 'static) -> Self
+    where
+        F: Future<Output = T> + 'static,
+    {
+        let f = Rc::new(f);
+        Self {
+            create: Box::new(move || {
+                let f = Rc::clone(&f);
+                Coroutine::restartable(move |handle, initial_value| f(handle, initial_value))
+            }),
+            idle: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Returns an idle coroutine from the pool or creates a new one if there is none.
+    pub fn get(&self) -> Pooled<Y, T, R> {
+        let coroutine = self
+            .idle
+            .borrow_mut()
+            .pop()
+            .unwrap_or_else(|| (self.create)());
+        Pooled {
+            coroutine: Some(coroutine),
+            idle: Rc::clone(&self.idle),
+        }
+    }
+
+    /// Returns the number of idle coroutines in the pool.
+    pub fn idle(&self) -> usize {
+        self.idle.borrow().len()
+    }
+
+    /// Drops all idle coroutines.
+    pub fn clear(&self) {
+        self.idle.borrow_mut().clear();
+    }
+}
+
+/// A coroutine borrowed from a [`CoroutinePool`], which returns to the pool when dropped.
+pub struct Pooled<Y, T, R> {
+    // Only `None` while dropping or after `into_inner`
+    coroutine: Option<Coroutine<'static, Y, T, R>>,
+    idle: Rc<RefCell<Vec<Coroutine<'static, Y, T, R>>>>,
+}
+
+impl<Y, T, R> Pooled<Y, T, R> {
+    /// Takes the coroutine out of the pool for good.
+    pub fn into_inner(mut self) -> Coroutine<'static, Y, T, R> {
+        self.coroutine.take().unwrap()
+    }
+}
+
+impl<Y, T, R> Deref for Pooled<Y, T, R> {
+    type Target = Coroutine<'static, Y, T, R>;
+
+    fn deref(&self) -> &Self::Target {
+        self.coroutine.as_ref().unwrap()
+    }
+}
+
+impl<Y, T, R> DerefMut for Pooled<Y, T, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.coroutine.as_mut().unwrap()
+    }
+}
+
+impl<Y, T, R> Drop for Pooled<Y, T, R> {
+    fn drop(&mut self) {
+        if let Some(mut coroutine) = self.coroutine.take() {
+            coroutine.reset();
+            coroutine.stats = Stats::default();
+            self.idle.borrow_mut().push(coroutine);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoroutinePool;
+    use crate::State;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_pool_reuse() {
+        let pool = CoroutinePool::new(|handle, start: i32| async move {
+            let next = handle.yield_(start).await;
+            start + next
+        });
+
+        let mut first = pool.get();
+        let shared = Rc::as_ptr(&first.yield_handle.shared);
+        assert_eq!(first.resume_with(1), State::Yield(1));
+        drop(first);
+        assert_eq!(pool.idle(), 1);
+
+        let mut second = pool.get();
+        assert_eq!(Rc::as_ptr(&second.yield_handle.shared), shared);
+        assert_eq!(second.stats().resumes, 0);
+        assert_eq!(second.resume_with(10), State::Yield(10));
+        assert_eq!(second.resume_with(5), State::Complete(15));
+        assert_eq!(pool.idle(), 0);
+
+        let detached = second.into_inner();
+        drop(detached);
+        assert_eq!(pool.idle(), 0);
+    }
+}
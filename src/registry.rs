@@ -0,0 +1,175 @@
+//! An opt-in registry of the live coroutines on the current thread.
+//!
+//! Coroutines that were created with [`CoroutineBuilder::register`](crate::CoroutineBuilder::register)
+//! are listed by [`iter`] until they are dropped, e.g. to show in a debug overlay which scripts are
+//! suspended and where they were created.
+//!
+//! ```
+//! use async_coroutine::{
+//!     Generator,
+//!     registry::{self, Status},
+//! };
+//!
+//! let mut generator = Generator::builder()
+//!     .name("blink")
+//!     .register()
+//!     .build(|handle, ()| async move {
+//!         handle.yield_(true).await;
+//!     });
+//! generator.resume();
+//!
+//! let entry = registry::iter().find(|entry| entry.name.as_deref() == Some("blink")).unwrap();
+//! assert_eq!(entry.status, Status::Suspended);
+//! assert_eq!(entry.stats.resumes, 1);
+//!
+//! drop(generator);
+//! assert!(registry::iter().all(|entry| entry.name.as_deref() != Some("blink")));
+//! ```
+
+use crate::{Coroutine, Stats};
+use std::{
+    cell::RefCell,
+    panic::Location,
+    rc::{Rc, Weak},
+};
+
+/// The status of a registered coroutine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Status {
+    /// The coroutine has not been resumed yet.
+    Created,
+    /// A resume of the coroutine is in progress, either because its body is running or because a
+    /// budgeted or timed resume was interrupted.
+    Running,
+    /// The coroutine is suspended and waits to be resumed.
+    Suspended,
+    /// The body of the coroutine has completed.
+    Completed,
+    /// The body of the coroutine panicked.
+    Poisoned,
+}
+
+/// A snapshot of a registered coroutine, returned by [`iter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The name of the coroutine, if it was given one.
+    pub name: Option<String>,
+    /// The location in the source code where the coroutine was created.
+    pub location: &'static Location<'static>,
+    /// The status of the coroutine.
+    pub status: Status,
+    /// The runtime statistics of the coroutine.
+    pub stats: Stats,
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Vec<Weak<RefCell<Entry>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns a snapshot of the registered coroutines of the current thread that are still alive, in
+/// the order they were created.
+pub fn iter() -> impl Iterator<Item = Entry> {
+    REGISTRY.with_borrow_mut(|registry| {
+        let mut entries = Vec::with_capacity(registry.len());
+        registry.retain(|entry| match entry.upgrade() {
+            Some(entry) => {
+                entries.push(entry.borrow().clone());
+                true
+            }
+            None => false,
+        });
+        entries.into_iter()
+    })
+}
+
+// Adds a coroutine to the registry, it is removed once the returned entry is dropped
+pub(crate) fn register(
+    name: Option<String>,
+    location: &'static Location<'static>,
+) -> Rc<RefCell<Entry>> {
+    let entry = Rc::new(RefCell::new(Entry {
+        name,
+        location,
+        status: Status::Created,
+        stats: Stats::default(),
+    }));
+    REGISTRY.with_borrow_mut(|registry| registry.push(Rc::downgrade(&entry)));
+    entry
+}
+
+impl<Y, T, R> Coroutine<'_, Y, T, R> {
+    // Updates the registry entry of the coroutine, if it is registered
+    pub(crate) fn update_registration(&self) {
+        let Some(entry) = &self.yield_handle.shared.registration else {
+            return;
+        };
+        let status = if self.poisoned {
+            Status::Poisoned
+        } else if self.in_progress {
+            Status::Running
+        } else if self.completed {
+            Status::Completed
+        } else if self.executor.executor.is_none() {
+            Status::Created
+        } else {
+            Status::Suspended
+        };
+
+        let mut entry = entry.borrow_mut();
+        entry.status = status;
+        entry.stats = self.stats;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Status, iter};
+    use crate::{Coroutine, Generator, State};
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn status(name: &str) -> Option<Status> {
+        iter()
+            .find(|entry| entry.name.as_deref() == Some(name))
+            .map(|entry| entry.status)
+    }
+
+    #[test]
+    fn test_registry_status() {
+        let mut co =
+            Coroutine::builder()
+                .name("script")
+                .register()
+                .build(|handle, ()| async move {
+                    assert_eq!(status("script"), Some(Status::Running));
+                    handle.yield_(1).await;
+                    handle.yield_(2).await;
+                });
+        let _unregistered = Generator::<(), ()>::builder()
+            .name("unregistered")
+            .build(|_handle, ()| async {});
+
+        assert_eq!(status("script"), Some(Status::Created));
+        assert_eq!(co.resume_with(()), State::Yield(1));
+        assert_eq!(status("script"), Some(Status::Suspended));
+        assert_eq!(co.resume_with_budget((), 0), Err(crate::BudgetExceeded));
+        assert_eq!(status("script"), Some(Status::Running));
+        assert_eq!(co.poll_with_budget(1), Ok(State::Yield(2)));
+        assert_eq!(co.resume_with(()), State::Complete(()));
+        assert_eq!(status("script"), Some(Status::Completed));
+        assert_eq!(status("unregistered"), None);
+    }
+
+    #[test]
+    fn test_registry_poisoned() {
+        let mut generator = Generator::<(), ()>::builder()
+            .name("crashing")
+            .register()
+            .build(|_handle, ()| async { panic!("crash") });
+
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| generator.resume()));
+        assert_eq!(status("crashing"), Some(Status::Poisoned));
+        drop(generator);
+        assert_eq!(status("crashing"), None);
+    }
+}
@@ -0,0 +1,184 @@
+//! Recording the inputs and outputs of a coroutine and replaying them deterministically.
+
+use crate::{Coroutine, Resumable, State};
+use std::fmt::Debug;
+
+impl<'a, Y, T, R> Coroutine<'a, Y, T, R> {
+    /// Wraps the coroutine in a [`Recorder`] that logs every resume value and yielded value.
+    pub fn record(self) -> Recorder<'a, Y, T, R> {
+        Recorder {
+            inner: self,
+            recording: Recording {
+                resumes: Vec::new(),
+                yields: Vec::new(),
+                complete: false,
+            },
+        }
+    }
+}
+
+/// A coroutine wrapper that logs every resume value and yielded value, created by
+/// [`Coroutine::record`].
+pub struct Recorder<'a, Y, T, R> {
+    inner: Coroutine<'a, Y, T, R>,
+    recording: Recording<Y, R>,
+}
+
+impl<Y, T, R> Recorder<'_, Y, T, R>
+where
+    Y: Clone,
+    R: Clone,
+{
+    /// Resumes the underlying coroutine and records the resume value and the yielded value.
+    pub fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        self.recording.resumes.push(resume.clone());
+        let state = self.inner.resume_with(resume);
+        match &state {
+            State::Yield(value) => self.recording.yields.push(value.clone()),
+            State::Complete(_) => self.recording.complete = true,
+        }
+        state
+    }
+}
+
+impl<Y, T, R> Resumable<R> for Recorder<'_, Y, T, R>
+where
+    Y: Clone,
+    R: Clone,
+{
+    type Yield = Y;
+    type Complete = T;
+
+    fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        Recorder::resume_with(self, resume)
+    }
+}
+
+impl<Y, T, R> Recorder<'_, Y, T, R> {
+    /// Returns the recording so far.
+    pub fn recording(&self) -> &Recording<Y, R> {
+        &self.recording
+    }
+
+    /// Consumes the recorder, returning the recording.
+    pub fn into_recording(self) -> Recording<Y, R> {
+        self.recording
+    }
+}
+
+/// The resume values and yielded values of a coroutine, recorded by a [`Recorder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording<Y, R> {
+    resumes: Vec<R>,
+    yields: Vec<Y>,
+    complete: bool,
+}
+
+impl<Y, R> Recording<Y, R> {
+    /// Returns the recorded resume values.
+    pub fn resumes(&self) -> &[R] {
+        &self.resumes
+    }
+
+    /// Returns the recorded yielded values.
+    pub fn yields(&self) -> &[Y] {
+        &self.yields
+    }
+
+    /// Returns `true` if the coroutine completed during the recording.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+/// Re-drives fresh coroutines with the resume values of a [`Recording`], asserting that they
+/// yield the recorded values.
+#[derive(Debug, Clone)]
+pub struct Replayer<Y, R> {
+    recording: Recording<Y, R>,
+}
+
+impl<Y, R> Replayer<Y, R>
+where
+    Y: Debug + PartialEq,
+    R: Clone,
+{
+    /// Creates a replayer for the recording.
+    pub fn new(recording: Recording<Y, R>) -> Self {
+        Self { recording }
+    }
+
+    /// Resumes the coroutine with every recorded resume value, returning the completion value if
+    /// the recording completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine yields a value that differs from the recorded one, or if it
+    /// completes at a different point than during the recording.
+    pub fn replay<T>(&self, mut coroutine: Coroutine<'_, Y, T, R>) -> Option<T> {
+        let steps = self.recording.resumes.len();
+        for (step, resume) in self.recording.resumes.iter().enumerate() {
+            let complete = step == steps - 1 && self.recording.complete;
+            match coroutine.resume_with(resume.clone()) {
+                State::Yield(value) => {
+                    assert!(
+                        !complete,
+                        "replay diverged at step {step}: expected completion, got yield {value:?}"
+                    );
+                    assert_eq!(
+                        value, self.recording.yields[step],
+                        "replay diverged at step {step}"
+                    );
+                }
+                State::Complete(value) => {
+                    assert!(
+                        complete,
+                        "replay diverged at step {step}: expected yield {:?}, got completion",
+                        self.recording.yields[step]
+                    );
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Replayer;
+    use crate::{Coroutine, State};
+
+    fn new(offset: i32) -> Coroutine<'static, i32, i32, i32> {
+        Coroutine::new(move |handle, mut sum| async move {
+            while sum < 10 {
+                sum += handle.yield_(sum + offset).await;
+            }
+            sum
+        })
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let mut recorder = new(0).record();
+        assert_eq!(recorder.resume_with(1), State::Yield(1));
+        assert_eq!(recorder.resume_with(4), State::Yield(5));
+        assert_eq!(recorder.resume_with(5), State::Complete(10));
+
+        let recording = recorder.into_recording();
+        assert_eq!(recording.resumes(), [1, 4, 5]);
+        assert_eq!(recording.yields(), [1, 5]);
+        assert!(recording.is_complete());
+
+        assert_eq!(Replayer::new(recording).replay(new(0)), Some(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "replay diverged at step 0")]
+    fn test_replay_diverged() {
+        let mut recorder = new(0).record();
+        recorder.resume_with(1);
+
+        Replayer::new(recorder.into_recording()).replay(new(1));
+    }
+}
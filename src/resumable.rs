@@ -0,0 +1,92 @@
+use crate::State;
+
+/// A type-erased [`Resumable`], created by [`Resumable::boxed`].
+///
+/// This allows storing heterogeneous coroutines and adapters with the same yield, completion and
+/// resume types in one collection.
+pub type BoxCoroutine<Y, T, R> = Box<dyn Resumable<R, Yield = Y, Complete = T>>;
+
+/// A value that can be resumed with an `R` like a [`Coroutine`](crate::Coroutine).
+///
+/// This is implemented by [`Coroutine`](crate::Coroutine) and by every adapter, so drivers,
+/// schedulers and test harnesses can be written once against this trait. The trait is object safe,
+/// see [`BoxCoroutine`].
+pub trait Resumable<R> {
+    /// The type of the yielded values.
+    type Yield;
+    /// The type of the completion value.
+    type Complete;
+
+    /// Resumes with a value of type `R`.
+    fn resume_with(&mut self, resume: R) -> State<Self::Yield, Self::Complete>;
+
+    /// Erases the type, returning a [`BoxCoroutine`].
+    fn boxed(self) -> BoxCoroutine<Self::Yield, Self::Complete, R>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+impl<C, R> Resumable<R> for Box<C>
+where
+    C: Resumable<R> + ?Sized,
+{
+    type Yield = C::Yield;
+    type Complete = C::Complete;
+
+    fn resume_with(&mut self, resume: R) -> State<Self::Yield, Self::Complete> {
+        (**self).resume_with(resume)
+    }
+}
+
+/// Forwards to whichever coroutine is present, so conditionally constructed coroutines of different
+/// types can be driven without boxing.
+#[cfg(feature = "either")]
+impl<A, B, R> Resumable<R> for either::Either<A, B>
+where
+    A: Resumable<R>,
+    B: Resumable<R, Yield = A::Yield, Complete = A::Complete>,
+{
+    type Yield = A::Yield;
+    type Complete = A::Complete;
+
+    fn resume_with(&mut self, resume: R) -> State<Self::Yield, Self::Complete> {
+        match self {
+            either::Either::Left(left) => left.resume_with(resume),
+            either::Either::Right(right) => right.resume_with(resume),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "either"))]
+mod tests {
+    use super::Resumable;
+    use crate::{Generator, State};
+    use either::Either;
+
+    #[test]
+    fn test_either() {
+        let new = |dedup: bool| {
+            let generator = Generator::new(|handle, ()| async move {
+                handle.yield_(1).await;
+                handle.yield_(1).await;
+            });
+            if dedup {
+                Either::Left(generator.dedup())
+            } else {
+                Either::Right(generator)
+            }
+        };
+
+        let mut co = new(true);
+        assert_eq!(co.resume_with(()), State::Yield(1));
+        assert_eq!(co.resume_with(()), State::Complete(()));
+
+        let mut co = new(false);
+        assert_eq!(co.resume_with(()), State::Yield(1));
+        assert_eq!(co.resume_with(()), State::Yield(1));
+        assert_eq!(co.resume_with(()), State::Complete(()));
+    }
+}
@@ -0,0 +1,265 @@
+//! Helpers for game scripts that are resumed once per frame.
+//!
+//! Script coroutines are resumed with a value describing the frame, which implements [`Tick`] if
+//! the helpers need to know the time that passed. The wait helpers are for scripts that yield
+//! `()`, [`tween`] yields an interpolated value every frame, e.g. to animate a property.
+
+use crate::YieldHandle;
+use std::time::Duration;
+
+/// A resume value that reports the time that passed since the previous frame.
+pub trait Tick {
+    /// Returns the time that passed since the previous frame.
+    fn delta(&self) -> Duration;
+}
+
+impl Tick for Duration {
+    fn delta(&self) -> Duration {
+        *self
+    }
+}
+
+/// Seconds.
+impl Tick for f32 {
+    fn delta(&self) -> Duration {
+        Duration::from_secs_f32(*self)
+    }
+}
+
+/// Seconds.
+impl Tick for f64 {
+    fn delta(&self) -> Duration {
+        Duration::from_secs_f64(*self)
+    }
+}
+
+#[cfg(feature = "bevy")]
+impl<T> Tick for bevy_time::Time<T>
+where
+    T: Default,
+{
+    fn delta(&self) -> Duration {
+        bevy_time::Time::delta(self)
+    }
+}
+
+/// A value that can be interpolated by [`tween`].
+pub trait Lerp {
+    /// Interpolates linearly between `self` at `t = 0` and `to` at `t = 1`.
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * f64::from(t)
+    }
+}
+
+impl<V, const N: usize> Lerp for [V; N]
+where
+    V: Lerp,
+{
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        std::array::from_fn(|index| self[index].lerp(&to[index], t))
+    }
+}
+
+/// Easing functions for [`tween`], which map the linear progress in `0..=1` to the eased one.
+pub mod easing {
+    /// Constant speed.
+    pub fn linear(t: f32) -> f32 {
+        t
+    }
+
+    /// Starts slow and accelerates.
+    pub fn ease_in_quad(t: f32) -> f32 {
+        t * t
+    }
+
+    /// Starts fast and decelerates.
+    pub fn ease_out_quad(t: f32) -> f32 {
+        t * (2.0 - t)
+    }
+
+    /// Accelerates until halfway and decelerates afterwards.
+    pub fn ease_in_out_quad(t: f32) -> f32 {
+        if t < 0.5 {
+            2.0 * t * t
+        } else {
+            -1.0 + (4.0 - 2.0 * t) * t
+        }
+    }
+}
+
+/// Suspends the script for `frames` frames.
+pub async fn wait_frames<R>(handle: &YieldHandle<(), R>, frames: usize) {
+    for _ in 0..frames {
+        handle.yield_(()).await;
+    }
+}
+
+/// Suspends the script until at least `seconds` have passed according to the resume values.
+///
+/// Returns the time by which the last frame overshot the wait. A wait of zero, negative or `NaN`
+/// seconds returns immediately without suspending, an infinite one never returns.
+pub async fn wait_seconds<R>(handle: &YieldHandle<(), R>, seconds: f32) -> Duration
+where
+    R: Tick,
+{
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Duration::ZERO;
+    }
+    if seconds.is_infinite() {
+        loop {
+            handle.yield_(()).await;
+        }
+    }
+    let mut remaining = Duration::try_from_secs_f32(seconds).unwrap_or(Duration::MAX);
+    loop {
+        let delta = handle.yield_(()).await.delta();
+        match remaining.checked_sub(delta) {
+            Some(rest) if !rest.is_zero() => remaining = rest,
+            _ => break delta - remaining,
+        }
+    }
+}
+
+/// Suspends the script until it is resumed with a value that satisfies `predicate`, which is
+/// returned.
+pub async fn wait_until<R>(
+    handle: &YieldHandle<(), R>,
+    mut predicate: impl FnMut(&R) -> bool,
+) -> R {
+    loop {
+        let resume = handle.yield_(()).await;
+        if predicate(&resume) {
+            break resume;
+        }
+    }
+}
+
+/// Yields values interpolated from `from` to `to` once per frame, until `seconds` have passed
+/// according to the resume values.
+///
+/// The first value is `from` and the last one is `to`, `easing` maps the linear progress to the
+/// interpolation factor (see [`easing`]).
+pub async fn tween<V, R>(
+    handle: &YieldHandle<V, R>,
+    from: V,
+    to: V,
+    seconds: f32,
+    easing: impl Fn(f32) -> f32,
+) where
+    V: Lerp,
+    R: Tick,
+{
+    let mut elapsed = Duration::ZERO;
+    loop {
+        let progress = if seconds > 0.0 {
+            elapsed.as_secs_f32() / seconds
+        } else {
+            1.0
+        };
+        if progress >= 1.0 {
+            handle.yield_(to).await;
+            break;
+        }
+        elapsed += handle
+            .yield_(from.lerp(&to, easing(progress)))
+            .await
+            .delta();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{easing, tween, wait_frames, wait_seconds, wait_until};
+    use crate::{Coroutine, State};
+    use std::time::Duration;
+
+    #[test]
+    fn test_wait_frames() {
+        let mut co = Coroutine::new(|handle, ()| async move {
+            wait_frames(&handle, 2).await;
+            "Done"
+        });
+
+        assert_eq!(co.resume_with(()), State::Yield(()));
+        assert_eq!(co.resume_with(()), State::Yield(()));
+        assert_eq!(co.resume_with(()), State::Complete("Done"));
+    }
+
+    #[test]
+    fn test_wait_seconds() {
+        let mut co =
+            Coroutine::new(
+                |handle, _delta: Duration| async move { wait_seconds(&handle, 1.0).await },
+            );
+
+        let frame = Duration::from_millis(400);
+        assert_eq!(co.resume_with(frame), State::Yield(()));
+        assert_eq!(co.resume_with(frame), State::Yield(()));
+        assert_eq!(co.resume_with(frame), State::Yield(()));
+        assert_eq!(
+            co.resume_with(frame),
+            State::Complete(Duration::from_millis(200))
+        );
+    }
+
+    #[test]
+    fn test_wait_seconds_degenerate() {
+        for seconds in [0.0, -1.0, f32::NAN] {
+            let mut co = Coroutine::new(move |handle, _delta: Duration| async move {
+                wait_seconds(&handle, seconds).await
+            });
+            assert_eq!(
+                co.resume_with(Duration::ZERO),
+                State::Complete(Duration::ZERO)
+            );
+        }
+
+        let mut co = Coroutine::new(|handle, _delta: Duration| async move {
+            wait_seconds(&handle, f32::INFINITY).await
+        });
+        for _ in 0..3 {
+            assert_eq!(co.resume_with(Duration::MAX), State::Yield(()));
+        }
+    }
+
+    #[test]
+    fn test_wait_until() {
+        let mut co = Coroutine::new(|handle, _frame: u32| async move {
+            wait_until(&handle, |frame| frame % 3 == 0).await
+        });
+
+        assert!(co.resume_with(1).is_yield());
+        assert!(co.resume_with(2).is_yield());
+        assert!(co.resume_with(4).is_yield());
+        assert_eq!(co.resume_with(6), State::Complete(6));
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut co = Coroutine::new(|handle, _delta: f32| async move {
+            tween(&handle, 0.0, 4.0, 1.0, easing::linear).await;
+        });
+        assert_eq!(
+            co.drive_with([0.0, 0.25, 0.25, 0.25, 0.25, 0.0]),
+            (vec![0.0, 1.0, 2.0, 3.0, 4.0], Some(()))
+        );
+
+        let mut co = Coroutine::new(|handle, _delta: f32| async move {
+            tween(&handle, [0.0, 8.0], [4.0, 0.0], 1.0, easing::ease_in_quad).await;
+        });
+        assert_eq!(
+            co.drive_with([0.0, 0.5, 0.5, 0.0]),
+            (vec![[0.0, 8.0], [1.0, 6.0], [4.0, 0.0]], Some(()))
+        );
+    }
+}
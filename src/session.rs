@@ -0,0 +1,230 @@
+//! Session-typed coroutines whose sequence of yield and resume types is checked at compile time.
+//!
+//! A protocol is described by nesting [`Step`]s that end in [`Done`]. The body of the coroutine
+//! receives a [`Session`] and the driver a [`Driver`], both of which change their type after every
+//! exchange, so exchanging values in the wrong order or of the wrong type fails to compile.
+//!
+//! ```
+//! use async_coroutine::session::{Done, Driver, Step};
+//!
+//! type Negotiation = Step<u32, bool, Step<String, (), Done<usize>>>;
+//!
+//! let driver = Driver::<Negotiation>::new(|session| async move {
+//!     let (accepted, session) = session.yield_(42).await;
+//!     let data = if accepted { "accepted" } else { "rejected" };
+//!     let ((), session) = session.yield_(data.to_string()).await;
+//!     session.finish(data.len())
+//! });
+//!
+//! let (offer, reply) = driver.receive();
+//! assert_eq!(offer, 42);
+//! let (data, reply) = reply.reply(true).receive();
+//! assert_eq!(data, "accepted");
+//! assert_eq!(reply.reply(()).finish(), 8);
+//! ```
+
+use crate::{Coroutine, State, YieldHandle};
+use std::{any::Any, future::Future, marker::PhantomData};
+
+type Erased = Box<dyn Any>;
+
+/// A protocol step in which the coroutine yields a `Y`, is resumed with an `R` and continues with
+/// the protocol `N`.
+#[expect(clippy::type_complexity)]
+pub struct Step<Y, R, N>(PhantomData<fn() -> (Y, R, N)>);
+
+/// The end of a protocol, the coroutine completes with a `T`.
+pub struct Done<T>(PhantomData<fn() -> T>);
+
+/// A protocol built from [`Step`] and [`Done`].
+pub trait Protocol: 'static {
+    /// The type the coroutine completes with.
+    type Output: 'static;
+}
+
+impl<Y, R, N> Protocol for Step<Y, R, N>
+where
+    Y: 'static,
+    R: 'static,
+    N: Protocol,
+{
+    type Output = N::Output;
+}
+
+impl<T> Protocol for Done<T>
+where
+    T: 'static,
+{
+    type Output = T;
+}
+
+/// The body-facing side of a session-typed coroutine at protocol state `P`.
+pub struct Session<P> {
+    handle: YieldHandle<Erased, Erased>,
+    _protocol: PhantomData<P>,
+}
+
+impl<Y, R, N> Session<Step<Y, R, N>>
+where
+    Y: 'static,
+    R: 'static,
+{
+    /// Yields a value and receives back the resume value together with the session for the rest of
+    /// the protocol.
+    pub async fn yield_(self, value: Y) -> (R, Session<N>) {
+        let resume = self.handle.yield_(Box::new(value)).await;
+        let resume = *resume.downcast::<R>().expect("session protocol violated");
+        let session = Session {
+            handle: self.handle,
+            _protocol: PhantomData,
+        };
+        (resume, session)
+    }
+}
+
+impl<T> Session<Done<T>> {
+    /// Completes the protocol with a value.
+    pub fn finish(self, value: T) -> Finished<T> {
+        Finished(value)
+    }
+}
+
+/// Proof that the body of a session-typed coroutine completed its protocol, created by
+/// [`Session::finish`].
+pub struct Finished<T>(T);
+
+/// The driver-facing side of a session-typed coroutine at protocol state `P`.
+pub struct Driver<P> {
+    coroutine: Coroutine<'static, Erased, Erased, Erased>,
+    state: Option<State<Erased, Erased>>,
+    _protocol: PhantomData<P>,
+}
+
+impl<P> Driver<P>
+where
+    P: Protocol,
+{
+    /// Creates a new session-typed coroutine from a function that takes the [`Session`] and
+    /// returns a future that finishes the protocol.
+    #[track_caller]
+    pub fn new<F>(f: impl FnOnce(Session<P>) -> F + 'static) -> Self
+    where
+        F: Future<Output = Finished<P::Output>> + 'static,
+    {
+        let coroutine = Coroutine::new(move |handle, _initial| async move {
+            let session = Session {
+                handle,
+                _protocol: PhantomData,
+            };
+            let Finished(value) = f(session).await;
+            Box::new(value) as Erased
+        });
+        Self {
+            coroutine,
+            state: None,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<P> Driver<P> {
+    fn into_state(
+        mut self,
+    ) -> (
+        State<Erased, Erased>,
+        Coroutine<'static, Erased, Erased, Erased>,
+    ) {
+        let state = match self.state.take() {
+            Some(state) => state,
+            // The protocol has not been started yet
+            None => self.coroutine.resume_with(Box::new(())),
+        };
+        (state, self.coroutine)
+    }
+}
+
+impl<Y, R, N> Driver<Step<Y, R, N>>
+where
+    Y: 'static,
+{
+    /// Receives the value the coroutine yields at this step together with a [`Reply`] for resuming
+    /// it.
+    pub fn receive(self) -> (Y, Reply<R, N>) {
+        let (state, coroutine) = self.into_state();
+        let State::Yield(value) = state else {
+            panic!("session protocol violated");
+        };
+        let value = *value.downcast::<Y>().expect("session protocol violated");
+        let reply = Reply {
+            coroutine,
+            _protocol: PhantomData,
+        };
+        (value, reply)
+    }
+}
+
+impl<T> Driver<Done<T>>
+where
+    T: 'static,
+{
+    /// Returns the value the coroutine completed the protocol with.
+    pub fn finish(self) -> T {
+        let (state, _) = self.into_state();
+        let State::Complete(value) = state else {
+            panic!("session protocol violated");
+        };
+        *value.downcast::<T>().expect("session protocol violated")
+    }
+}
+
+/// Resumes a session-typed coroutine with an `R` and continues with the protocol `N`, created by
+/// [`Driver::receive`].
+pub struct Reply<R, N> {
+    coroutine: Coroutine<'static, Erased, Erased, Erased>,
+    _protocol: PhantomData<fn(R) -> N>,
+}
+
+impl<R, N> Reply<R, N>
+where
+    R: 'static,
+{
+    /// Resumes the coroutine with a value, returning the driver for the rest of the protocol.
+    pub fn reply(mut self, resume: R) -> Driver<N> {
+        let state = self.coroutine.resume_with(Box::new(resume));
+        Driver {
+            coroutine: self.coroutine,
+            state: Some(state),
+            _protocol: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Done, Driver, Step};
+
+    #[test]
+    fn test_session() {
+        type Countdown = Step<u8, u8, Step<u8, u8, Done<u8>>>;
+
+        let new = || {
+            Driver::<Countdown>::new(|session| async move {
+                let (a, session) = session.yield_(3).await;
+                let (b, session) = session.yield_(a - 1).await;
+                session.finish(b - 1)
+            })
+        };
+
+        let (value, reply) = new().receive();
+        assert_eq!(value, 3);
+        let (value, reply) = reply.reply(value).receive();
+        assert_eq!(value, 2);
+        assert_eq!(reply.reply(value).finish(), 1);
+    }
+
+    #[test]
+    fn test_session_done() {
+        let driver = Driver::<Done<&str>>::new(|session| async move { session.finish("Bye") });
+        assert_eq!(driver.finish(), "Bye");
+    }
+}
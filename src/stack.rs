@@ -0,0 +1,205 @@
+//! Coroutines that live in the stack frame of the caller instead of on the heap.
+//!
+//! A [`StackCoroutine`] is created with the [`stack_coroutine!`](crate::stack_coroutine) macro,
+//! which pins the body in place like [`std::pin::pin!`]. It does not allocate, but the coroutine
+//! can not outlive the scope it was created in. The body yields through a [`StackHandle`], which
+//! offers a subset of the [`YieldHandle`] API.
+//!
+//! [`YieldHandle`]: crate::YieldHandle
+
+use crate::{State, yield_now::yield_now};
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+/// A coroutine whose body is pinned in the stack frame of the caller, created by
+/// [`stack_coroutine!`](crate::stack_coroutine).
+///
+/// The fields are an implementation detail of the macro.
+pub struct StackCoroutine<'a, 'h, Y, T, R, C, F>
+where
+    C: FnOnce(StackHandle<'h, Y, R>, R) -> F,
+    F: Future<Output = T>,
+{
+    #[doc(hidden)]
+    pub slot: &'h Slot<Y, R>,
+    #[doc(hidden)]
+    pub init: Option<C>,
+    #[doc(hidden)]
+    pub body: Pin<&'a mut Option<F>>,
+}
+
+impl<'h, Y, T, R, C, F> StackCoroutine<'_, 'h, Y, T, R, C, F>
+where
+    C: FnOnce(StackHandle<'h, Y, R>, R) -> F,
+    F: Future<Output = T>,
+{
+    /// Resumes the coroutine with a value of type `R`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine has already completed.
+    pub fn resume_with(&mut self, resume: R) -> State<Y, T> {
+        if let Some(init) = self.init.take() {
+            self.body
+                .set(Some(init(StackHandle { slot: self.slot }, resume)));
+        } else {
+            assert!(
+                self.body.is_some(),
+                "stack coroutine was resumed after completion"
+            );
+            self.slot.resume.set(Some(resume));
+        }
+
+        // Keeps polling until the body yields or completes, like `SpinPolicy::Spin`
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            let body = self.body.as_mut().as_pin_mut().unwrap();
+            if let Poll::Ready(value) = body.poll(&mut context) {
+                self.body.set(None);
+                break State::Complete(value);
+            }
+            if let Some(value) = self.slot.value.take() {
+                break State::Yield(value);
+            }
+        }
+    }
+
+    /// Returns `true` if the coroutine has completed.
+    pub fn is_completed(&self) -> bool {
+        self.init.is_none() && self.body.is_none()
+    }
+}
+
+impl<'h, Y, T, C, F> StackCoroutine<'_, 'h, Y, T, (), C, F>
+where
+    C: FnOnce(StackHandle<'h, Y, ()>, ()) -> F,
+    F: Future<Output = T>,
+{
+    /// Resumes the generator.
+    pub fn resume(&mut self) -> State<Y, T> {
+        self.resume_with(())
+    }
+}
+
+impl<'h, Y, T, R, C, F> fmt::Debug for StackCoroutine<'_, 'h, Y, T, R, C, F>
+where
+    C: FnOnce(StackHandle<'h, Y, R>, R) -> F,
+    F: Future<Output = T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StackCoroutine")
+            .field("completed", &self.is_completed())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The values exchanged between a [`StackCoroutine`] and its body.
+///
+/// This is an implementation detail of [`stack_coroutine!`](crate::stack_coroutine).
+#[doc(hidden)]
+pub struct Slot<Y, R> {
+    value: Cell<Option<Y>>,
+    resume: Cell<Option<R>>,
+}
+
+impl<Y, R> Slot<Y, R> {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            value: Cell::new(None),
+            resume: Cell::new(None),
+        }
+    }
+}
+
+// Lets the compiler infer the parameter types of the closure
+#[doc(hidden)]
+pub fn init<'h, Y, R, C, F>(f: C) -> Option<C>
+where
+    Y: 'h,
+    R: 'h,
+    C: FnOnce(StackHandle<'h, Y, R>, R) -> F,
+{
+    Some(f)
+}
+
+/// The handle the body of a [`StackCoroutine`] yields values with.
+#[derive(Clone, Copy)]
+pub struct StackHandle<'a, Y, R = ()> {
+    slot: &'a Slot<Y, R>,
+}
+
+impl<Y, R> StackHandle<'_, Y, R> {
+    /// Yields a value from the coroutine and receives back the resume value when the coroutine is
+    /// resumed.
+    pub async fn yield_(&self, value: Y) -> R {
+        self.slot.value.set(Some(value));
+        yield_now().await;
+        self.slot
+            .resume
+            .take()
+            .expect("stack coroutine: expected resume value")
+    }
+}
+
+impl<R> StackHandle<'_, (), R> {
+    /// Suspends the consumer and receives the next resume value.
+    pub async fn recv(&self) -> R {
+        self.yield_(()).await
+    }
+}
+
+impl<Y, R> fmt::Debug for StackHandle<'_, Y, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StackHandle").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{State, stack_coroutine};
+
+    #[test]
+    fn test_stack_coroutine() {
+        let mut co = stack_coroutine!(|handle, mut total: i32| async move {
+            while total < 10 {
+                total += handle.yield_(total).await;
+            }
+            total
+        });
+
+        assert_eq!(co.resume_with(1), State::Yield(1));
+        assert_eq!(co.resume_with(4), State::Yield(5));
+        assert!(!co.is_completed());
+        assert_eq!(co.resume_with(5), State::Complete(10));
+        assert!(co.is_completed());
+    }
+
+    #[test]
+    fn test_stack_coroutine_borrows() {
+        let words = vec!["a".to_string(), "b".to_string()];
+        let words = &words;
+        let mut co = stack_coroutine!(|handle, ()| async move {
+            for word in words {
+                handle.yield_(word.as_str()).await;
+            }
+        });
+
+        assert_eq!(co.resume(), State::Yield("a"));
+        assert_eq!(co.resume(), State::Yield("b"));
+        assert_eq!(co.resume(), State::Complete(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "stack coroutine was resumed after completion")]
+    fn test_stack_coroutine_resume_after_completion() {
+        let mut co = stack_coroutine!(|_handle, ()| async {});
+        assert_eq!(co.resume(), State::<(), ()>::Complete(()));
+        co.resume();
+    }
+}
@@ -0,0 +1,286 @@
+// A coroutine that stores its body inline instead of behind a `Box<dyn Future>`
+
+use crate::{State, yield_now::yield_now};
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+/// The storage a [`StaticCoroutine`] exchanges yielded and resume values through.
+///
+/// With the `critical-section` feature, the values are accessed in a critical section, so the slot
+/// is `Sync` if they are `Send` and can be declared in a `static`.
+#[cfg_attr(
+    feature = "critical-section",
+    doc = r#"
+```
+use async_coroutine::{State, StaticCoroutine, StaticSlot};
+use std::pin::pin;
+
+static SLOT: StaticSlot<bool, ()> = StaticSlot::new();
+
+let mut blink = pin!(StaticCoroutine::new(&SLOT, |handle, ()| async move {
+    handle.yield_(true).await;
+    handle.yield_(false).await;
+}));
+
+assert_eq!(blink.as_mut().resume(), State::Yield(true));
+assert_eq!(blink.as_mut().resume(), State::Yield(false));
+```
+"#
+)]
+pub struct StaticSlot<Y, R> {
+    value: SlotCell<Y>,
+    resume: SlotCell<R>,
+}
+
+impl<Y, R> StaticSlot<Y, R> {
+    /// Creates an empty slot.
+    pub const fn new() -> Self {
+        Self {
+            value: SlotCell::new(),
+            resume: SlotCell::new(),
+        }
+    }
+}
+
+impl<Y, R> Default for StaticSlot<Y, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Y, R> fmt::Debug for StaticSlot<Y, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticSlot").finish_non_exhaustive()
+    }
+}
+
+/// The handle the body of a [`StaticCoroutine`] yields values with.
+pub struct StaticHandle<'a, Y, R = ()> {
+    slot: &'a StaticSlot<Y, R>,
+}
+
+impl<Y, R> StaticHandle<'_, Y, R> {
+    /// Yields a value of type `Y` and receives back a value of type `R`.
+    pub async fn yield_(&self, value: Y) -> R {
+        let previous = self.slot.value.replace(value);
+        assert!(
+            previous.is_none(),
+            "static coroutine: multiple values were yielded without awaiting them",
+        );
+        yield_now().await;
+        self.slot
+            .resume
+            .take()
+            .expect("static coroutine: expected resume value")
+    }
+}
+
+impl<Y, R> fmt::Debug for StaticHandle<'_, Y, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticHandle").finish_non_exhaustive()
+    }
+}
+
+/// A coroutine that stores its body inline, without allocating.
+///
+/// Unlike [`Coroutine`](crate::Coroutine), the concrete type of the body is part of the type, so the
+/// coroutine has to be pinned to be resumed, e.g. with [`pin!`](std::pin::pin) on the stack of a
+/// `main` that never returns. Yielded and resume values go through a [`StaticSlot`] that outlives
+/// the coroutine. In exchange, there are no builder options, observers or statistics.
+///
+/// The body is polled again right away if it awaits a future that does not go through
+/// [`StaticHandle::yield_`].
+///
+/// ```
+/// use async_coroutine::{State, StaticCoroutine, StaticSlot};
+/// use std::pin::pin;
+///
+/// let slot = StaticSlot::new();
+/// let mut blink = pin!(StaticCoroutine::new(&slot, |handle, ()| async move {
+///     for _ in 0..2 {
+///         handle.yield_(true).await;
+///         handle.yield_(false).await;
+///     }
+/// }));
+///
+/// assert_eq!(blink.as_mut().resume(), State::Yield(true));
+/// assert_eq!(blink.as_mut().resume(), State::Yield(false));
+/// ```
+pub struct StaticCoroutine<'a, Y, R, I, F> {
+    slot: &'a StaticSlot<Y, R>,
+    stage: Stage<I, F>,
+}
+
+enum Stage<I, F> {
+    Init(I),
+    Running(F),
+    Completed,
+}
+
+impl<'a, Y, R, I, F> StaticCoroutine<'a, Y, R, I, F>
+where
+    I: FnOnce(StaticHandle<'a, Y, R>, R) -> F,
+    F: Future,
+{
+    /// Creates a new coroutine from a function that takes the [`StaticHandle`] and the initial
+    /// value and returns the body.
+    pub const fn new(slot: &'a StaticSlot<Y, R>, init: I) -> Self {
+        Self {
+            slot,
+            stage: Stage::Init(init),
+        }
+    }
+
+    /// Resumes the coroutine with a value of type `R`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coroutine has already completed.
+    #[track_caller]
+    pub fn resume_with(self: Pin<&mut Self>, resume: R) -> State<Y, F::Output> {
+        // SAFETY: The body is never moved out of `stage`, it is only dropped in place
+        let this = unsafe { self.get_unchecked_mut() };
+        match &this.stage {
+            Stage::Init(_) => {
+                let Stage::Init(init) = mem::replace(&mut this.stage, Stage::Completed) else {
+                    unreachable!();
+                };
+                let handle = StaticHandle { slot: this.slot };
+                this.stage = Stage::Running(init(handle, resume));
+            }
+            Stage::Running(_) => {
+                this.slot.resume.replace(resume);
+            }
+            Stage::Completed => panic!("static coroutine was resumed after completion"),
+        }
+
+        let Stage::Running(body) = &mut this.stage else {
+            unreachable!();
+        };
+        // SAFETY: See above
+        let mut body = unsafe { Pin::new_unchecked(body) };
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            match body.as_mut().poll(&mut context) {
+                Poll::Ready(value) => {
+                    this.stage = Stage::Completed;
+                    break State::Complete(value);
+                }
+                Poll::Pending => {
+                    if let Some(value) = this.slot.value.take() {
+                        break State::Yield(value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the body has completed.
+    pub fn is_completed(&self) -> bool {
+        matches!(self.stage, Stage::Completed)
+    }
+}
+
+impl<'a, Y, I, F> StaticCoroutine<'a, Y, (), I, F>
+where
+    I: FnOnce(StaticHandle<'a, Y, ()>, ()) -> F,
+    F: Future,
+{
+    /// Resumes the underlying generator.
+    #[track_caller]
+    pub fn resume(self: Pin<&mut Self>) -> State<Y, F::Output> {
+        self.resume_with(())
+    }
+}
+
+impl<Y, R, I, F> fmt::Debug for StaticCoroutine<'_, Y, R, I, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self.stage {
+            Stage::Init(_) => "created",
+            Stage::Running(_) => "running",
+            Stage::Completed => "completed",
+        };
+        f.debug_struct("StaticCoroutine")
+            .field("stage", &stage)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(feature = "critical-section"))]
+struct SlotCell<T>(Cell<Option<T>>);
+
+#[cfg(not(feature = "critical-section"))]
+impl<T> SlotCell<T> {
+    const fn new() -> Self {
+        Self(Cell::new(None))
+    }
+
+    fn replace(&self, value: T) -> Option<T> {
+        self.0.replace(Some(value))
+    }
+
+    fn take(&self) -> Option<T> {
+        self.0.take()
+    }
+}
+
+// A `static` slot may be shared between threads and interrupt handlers
+#[cfg(feature = "critical-section")]
+struct SlotCell<T>(critical_section::Mutex<Cell<Option<T>>>);
+
+#[cfg(feature = "critical-section")]
+impl<T> SlotCell<T> {
+    const fn new() -> Self {
+        Self(critical_section::Mutex::new(Cell::new(None)))
+    }
+
+    fn replace(&self, value: T) -> Option<T> {
+        critical_section::with(|cs| self.0.borrow(cs).replace(Some(value)))
+    }
+
+    fn take(&self) -> Option<T> {
+        critical_section::with(|cs| self.0.borrow(cs).take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StaticCoroutine, StaticSlot};
+    use crate::State;
+    use std::pin::pin;
+
+    #[test]
+    fn test_static_coroutine() {
+        let slot = StaticSlot::new();
+        let mut co = pin!(StaticCoroutine::new(&slot, |handle, mut sum: u32| {
+            async move {
+                while sum < 10 {
+                    sum += handle.yield_(sum).await;
+                }
+                "done"
+            }
+        }));
+
+        assert!(!co.is_completed());
+        assert_eq!(co.as_mut().resume_with(1), State::Yield(1));
+        assert_eq!(co.as_mut().resume_with(4), State::Yield(5));
+        assert_eq!(co.as_mut().resume_with(5), State::Complete("done"));
+        assert!(co.is_completed());
+    }
+
+    #[test]
+    #[should_panic(expected = "static coroutine was resumed after completion")]
+    fn test_static_coroutine_resume_after_completion() {
+        let slot = StaticSlot::<(), ()>::new();
+        let mut generator = pin!(StaticCoroutine::new(&slot, |_handle, ()| async {}));
+
+        assert_eq!(generator.as_mut().resume(), State::Complete(()));
+        generator.as_mut().resume(); // This panics
+    }
+}
@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+/// Runtime statistics of a [`Coroutine`](crate::Coroutine), returned by
+/// [`Coroutine::stats`](crate::Coroutine::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of times the coroutine was resumed.
+    pub resumes: usize,
+    /// The number of times the body of the coroutine was polled.
+    pub polls: usize,
+    /// The number of values the coroutine yielded.
+    pub yields: usize,
+    /// The total time spent polling the body of the coroutine.
+    pub busy_time: Duration,
+}
@@ -0,0 +1,170 @@
+// Conversion of streams into generators and consumers into sinks, enabled by the `futures` feature
+
+use crate::{Completed, Consumer, Generator};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::{
+    pin::{Pin, pin},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+impl<'a, Y> Generator<'a, Y, ()>
+where
+    Y: 'a,
+{
+    /// Creates a generator that yields every item of `stream` and completes when it ends.
+    ///
+    /// Every resume blocks the current thread until the stream produces its next item, so the
+    /// stream must not depend on being polled by an async runtime on this thread.
+    #[track_caller]
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Y> + 'a,
+    {
+        Self::new(|handle, ()| async move {
+            let mut stream = pin!(stream);
+            let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+            let mut context = Context::from_waker(&waker);
+            loop {
+                match stream.as_mut().poll_next(&mut context) {
+                    Poll::Ready(Some(item)) => handle.yield_(item).await,
+                    Poll::Ready(None) => break,
+                    Poll::Pending => thread::park(),
+                }
+            }
+        })
+    }
+}
+
+/// Sends every item to the consumer by resuming it once.
+///
+/// The consumer is started with the first item. Once it completes, its completion value is dropped
+/// and sending further items fails with [`Completed`].
+impl<T, R> Sink<R> for Consumer<'_, T, R> {
+    type Error = Completed;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Completed>> {
+        Poll::Ready(if self.completed {
+            Err(Completed)
+        } else {
+            Ok(())
+        })
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: R) -> Result<(), Completed> {
+        let this = self.get_mut();
+        if this.completed {
+            return Err(Completed);
+        }
+        this.resume_with(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Completed>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Completed>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+// Unparks the thread that blocks on the stream
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Completed, Consumer, Generator, State};
+    use futures_core::Stream;
+    use futures_sink::Sink;
+    use std::{
+        cell::RefCell,
+        pin::Pin,
+        rc::Rc,
+        sync::mpsc,
+        task::{Context, Poll, Waker},
+        thread,
+    };
+
+    // Receives the values of a channel, waking the consumer from another thread
+    struct Channel {
+        receiver: mpsc::Receiver<i32>,
+        waker: mpsc::Sender<Waker>,
+    }
+
+    impl Stream for Channel {
+        type Item = i32;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<i32>> {
+            match self.receiver.try_recv() {
+                Ok(value) => Poll::Ready(Some(value)),
+                Err(mpsc::TryRecvError::Disconnected) => Poll::Ready(None),
+                Err(mpsc::TryRecvError::Empty) => {
+                    self.waker.send(cx.waker().clone()).unwrap();
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_stream() {
+        let (sender, receiver) = mpsc::channel();
+        let (waker_sender, waker_receiver) = mpsc::channel::<Waker>();
+        let producer = thread::spawn(move || {
+            for value in 1..=2 {
+                let waker = waker_receiver.recv().unwrap();
+                sender.send(value).unwrap();
+                waker.wake();
+            }
+        });
+
+        let mut generator = Generator::from_stream(Channel {
+            receiver,
+            waker: waker_sender,
+        });
+        assert_eq!(generator.resume(), State::Yield(1));
+        assert_eq!(generator.resume(), State::Yield(2));
+        producer.join().unwrap();
+        assert_eq!(generator.resume(), State::Complete(()));
+    }
+
+    #[test]
+    fn test_sink() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut consumer = Consumer::new({
+            let received = Rc::clone(&received);
+            move |handle, mut value| async move {
+                while value != 0 {
+                    received.borrow_mut().push(value);
+                    value = handle.recv().await;
+                }
+            }
+        });
+
+        let mut context = Context::from_waker(Waker::noop());
+        let mut sink = Pin::new(&mut consumer);
+        for value in [1, 2, 0] {
+            assert_eq!(sink.as_mut().poll_ready(&mut context), Poll::Ready(Ok(())));
+            sink.as_mut().start_send(value).unwrap();
+        }
+        assert_eq!(
+            sink.as_mut().poll_ready(&mut context),
+            Poll::Ready(Err(Completed))
+        );
+        assert_eq!(sink.start_send(3), Err(Completed));
+        assert_eq!(*received.borrow(), [1, 2]);
+    }
+}
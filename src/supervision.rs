@@ -0,0 +1,326 @@
+//! Supervising coroutines, i.e. restarting them after their body panicked.
+//!
+//! A [`Supervisor`] creates its coroutine from a factory and catches the panics of its body. The
+//! poisoned coroutine is replaced with a fresh one as allowed by the [`RestartPolicy`], so a
+//! crashing script does not take down the whole program.
+//!
+//! ```
+//! use async_coroutine::{
+//!     Coroutine, State,
+//!     supervision::{RestartPolicy, Supervisor, SupervisorError},
+//! };
+//!
+//! let mut supervisor = Supervisor::new(RestartPolicy::MaxRestarts(1), || {
+//!     Coroutine::new(|handle, mut value: i32| async move {
+//!         loop {
+//!             assert!(value >= 0, "negative value");
+//!             value = handle.yield_(value * 2).await;
+//!         }
+//!     })
+//! });
+//!
+//! assert_eq!(supervisor.resume_with(1), Ok(State::Yield(2)));
+//! assert!(matches!(supervisor.resume_with(-1), Err(SupervisorError::Panicked(_))));
+//! assert_eq!(supervisor.resume_with(3), Ok(State::Yield(6)));
+//! assert_eq!(supervisor.restarts(), 1);
+//! ```
+//!
+//! The panic is still reported by the panic hook, install a custom hook to silence it.
+
+use crate::{Coroutine, State};
+use std::{
+    any::Any,
+    error::Error,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+/// Determines whether a [`Supervisor`] restarts its coroutine after the body panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Restarts the coroutine after every panic.
+    Always,
+    /// Restarts the coroutine at most the given number of times, then gives up.
+    MaxRestarts(usize),
+    /// Restarts the coroutine after a delay, which starts at `initial` and doubles with every
+    /// restart up to `max`.
+    Backoff {
+        /// The delay before the first restart.
+        initial: Duration,
+        /// The maximum delay.
+        max: Duration,
+    },
+}
+
+/// The event passed to the hook installed with [`Supervisor::on_restart`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Restart {
+    /// The number of restarts so far, including this one.
+    pub restarts: usize,
+    /// The message of the panic that caused the restart.
+    pub message: String,
+}
+
+/// The error returned by [`Supervisor::resume_with`] if the coroutine could not be resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupervisorError {
+    /// The body panicked with the given message during this resume.
+    Panicked(String),
+    /// The coroutine is waiting for its restart, which is due in the given time. The resume value
+    /// was dropped.
+    BackingOff(Duration),
+    /// The coroutine panicked more often than the [`RestartPolicy`] allows.
+    GaveUp,
+}
+
+impl fmt::Display for SupervisorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Panicked(message) => write!(f, "coroutine panicked: {message}"),
+            Self::BackingOff(remaining) => {
+                write!(f, "coroutine restarts in {remaining:?}")
+            }
+            Self::GaveUp => f.write_str("coroutine panicked too often and was not restarted"),
+        }
+    }
+}
+
+impl Error for SupervisorError {}
+
+// `defmt` can not format a `Duration`
+#[cfg(feature = "defmt")]
+impl defmt::Format for SupervisorError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Panicked(message) => defmt::write!(f, "Panicked({=str})", message),
+            Self::BackingOff(remaining) => defmt::write!(
+                f,
+                "BackingOff({=u64} us)",
+                remaining.as_micros().try_into().unwrap_or(u64::MAX),
+            ),
+            Self::GaveUp => defmt::write!(f, "GaveUp"),
+        }
+    }
+}
+
+/// A coroutine that is restarted according to a [`RestartPolicy`] after its body panicked.
+pub struct Supervisor<Y, T, R> {
+    factory: Box<dyn FnMut() -> Coroutine<'static, Y, T, R>>,
+    policy: RestartPolicy,
+    coroutine: Option<Coroutine<'static, Y, T, R>>,
+    restarts: usize,
+    // The message of the panic the coroutine has not been restarted after yet
+    crashed: Option<String>,
+    restart_at: Option<Instant>,
+    #[expect(clippy::type_complexity)]
+    on_restart: Option<Box<dyn FnMut(&Restart)>>,
+}
+
+impl<Y, T, R> Supervisor<Y, T, R> {
+    /// Creates a supervisor that runs the coroutines created by `factory`.
+    pub fn new(
+        policy: RestartPolicy,
+        mut factory: impl FnMut() -> Coroutine<'static, Y, T, R> + 'static,
+    ) -> Self {
+        Self {
+            coroutine: Some(factory()),
+            factory: Box::new(factory),
+            policy,
+            restarts: 0,
+            crashed: None,
+            restart_at: None,
+            on_restart: None,
+        }
+    }
+
+    /// Sets a hook that is called every time the coroutine is restarted.
+    pub fn on_restart(mut self, on_restart: impl FnMut(&Restart) + 'static) -> Self {
+        self.on_restart = Some(Box::new(on_restart));
+        self
+    }
+
+    /// Returns how often the coroutine has been restarted.
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    /// Resumes the coroutine with a value of type `R`, restarting it first if it panicked before.
+    ///
+    /// Panics that are not caused by the body, e.g. resuming the coroutine after completion, are
+    /// propagated.
+    pub fn resume_with(&mut self, resume: R) -> Result<State<Y, T>, SupervisorError> {
+        if self.coroutine.is_none() {
+            self.restart()?;
+        }
+
+        let coroutine = self.coroutine.as_mut().unwrap();
+        match panic::catch_unwind(AssertUnwindSafe(|| coroutine.resume_with(resume))) {
+            Ok(state) => Ok(state),
+            Err(payload) if coroutine.is_poisoned() => {
+                let message = panic_message(&*payload);
+                self.coroutine = None;
+                self.crashed = Some(message.clone());
+                self.restart_at = self.delay().map(|delay| Instant::now() + delay);
+                Err(SupervisorError::Panicked(message))
+            }
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    // Replaces the crashed coroutine if the policy allows it
+    fn restart(&mut self) -> Result<(), SupervisorError> {
+        if let RestartPolicy::MaxRestarts(max) = self.policy
+            && self.restarts >= max
+        {
+            return Err(SupervisorError::GaveUp);
+        }
+        if let Some(restart_at) = self.restart_at {
+            let remaining = restart_at.saturating_duration_since(Instant::now());
+            if !remaining.is_zero() {
+                return Err(SupervisorError::BackingOff(remaining));
+            }
+        }
+
+        self.restarts += 1;
+        self.restart_at = None;
+        self.coroutine = Some((self.factory)());
+        let message = self.crashed.take().unwrap_or_default();
+        if let Some(on_restart) = &mut self.on_restart {
+            on_restart(&Restart {
+                restarts: self.restarts,
+                message,
+            });
+        }
+        Ok(())
+    }
+
+    // The delay before the next restart
+    fn delay(&self) -> Option<Duration> {
+        match self.policy {
+            RestartPolicy::Backoff { initial, max } => {
+                let factor = 2u32.saturating_pow(self.restarts.try_into().unwrap_or(u32::MAX));
+                Some(initial.saturating_mul(factor).min(max))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<Y, T> Supervisor<Y, T, ()> {
+    /// Resumes the underlying generator, see [`Supervisor::resume_with`].
+    pub fn resume(&mut self) -> Result<State<Y, T>, SupervisorError> {
+        self.resume_with(())
+    }
+}
+
+impl<Y, T, R> fmt::Debug for Supervisor<Y, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("policy", &self.policy)
+            .field("coroutine", &self.coroutine)
+            .field("restarts", &self.restarts)
+            .finish_non_exhaustive()
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Restart, RestartPolicy, Supervisor, SupervisorError};
+    use crate::{Coroutine, Generator, State};
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    fn crashing() -> Coroutine<'static, i32, (), i32> {
+        Coroutine::new(|handle, mut value| async move {
+            loop {
+                assert!(value != 0, "zero");
+                value = handle.yield_(value).await;
+            }
+        })
+    }
+
+    #[test]
+    fn test_supervisor_always() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut supervisor = Supervisor::new(RestartPolicy::Always, crashing).on_restart({
+            let events = Rc::clone(&events);
+            move |event| events.borrow_mut().push(event.clone())
+        });
+
+        for restarts in 1..=3 {
+            assert_eq!(supervisor.resume_with(1), Ok(State::Yield(1)));
+            assert_eq!(
+                supervisor.resume_with(0),
+                Err(SupervisorError::Panicked("zero".to_string()))
+            );
+            assert_eq!(supervisor.restarts(), restarts - 1);
+        }
+        assert_eq!(supervisor.resume_with(2), Ok(State::Yield(2)));
+        assert_eq!(supervisor.restarts(), 3);
+        assert_eq!(
+            events.borrow()[2],
+            Restart {
+                restarts: 3,
+                message: "zero".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_supervisor_max_restarts() {
+        let mut supervisor = Supervisor::new(RestartPolicy::MaxRestarts(1), crashing);
+
+        assert!(supervisor.resume_with(0).is_err());
+        assert!(supervisor.resume_with(0).is_err());
+        assert_eq!(supervisor.resume_with(1), Err(SupervisorError::GaveUp));
+        assert_eq!(supervisor.restarts(), 1);
+    }
+
+    #[test]
+    fn test_supervisor_backoff() {
+        let mut supervisor = Supervisor::new(
+            RestartPolicy::Backoff {
+                initial: Duration::from_millis(10),
+                max: Duration::from_millis(15),
+            },
+            crashing,
+        );
+
+        assert!(supervisor.resume_with(0).is_err());
+        assert!(matches!(
+            supervisor.resume_with(1),
+            Err(SupervisorError::BackingOff(remaining)) if remaining <= Duration::from_millis(10)
+        ));
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(supervisor.resume_with(1), Ok(State::Yield(1)));
+
+        // The delay doubles, but is capped
+        assert!(supervisor.resume_with(0).is_err());
+        assert!(matches!(
+            supervisor.resume_with(1),
+            Err(SupervisorError::BackingOff(remaining))
+                if remaining > Duration::from_millis(10) && remaining <= Duration::from_millis(15)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "was resumed after completion")]
+    fn test_supervisor_propagates_misuse() {
+        let mut supervisor = Supervisor::new(RestartPolicy::Always, || {
+            Generator::<(), ()>::new(|_handle, ()| async {})
+        });
+
+        assert_eq!(supervisor.resume(), Ok(State::Complete(())));
+        let _ = supervisor.resume(); // This panics
+    }
+}
@@ -0,0 +1,261 @@
+//! Assertions for testing coroutines.
+//!
+//! The [`assert_yields!`] and [`assert_completes!`] macros drive a coroutine and compare the
+//! observed values with the expected ones, printing both on failure. [`MockCoroutine`] creates
+//! coroutines that play back a script, for testing drivers and adapters.
+
+pub use crate::{assert_completes, assert_yields};
+
+use crate::{Coroutine, Generator, State, YieldHandle};
+use std::fmt::Debug;
+
+/// Resumes a generator once for every expected value and asserts that it yields exactly these
+/// values.
+///
+/// ```
+/// use async_coroutine::{Generator, assert_yields};
+///
+/// let mut generator = Generator::new(|handle, ()| async move {
+///     handle.yield_(1).await;
+///     handle.yield_(2).await;
+/// });
+///
+/// assert_yields!(generator, [1, 2]);
+/// ```
+#[macro_export]
+macro_rules! assert_yields {
+    ($generator:expr, [$($value:expr),* $(,)?] $(,)?) => {
+        $crate::testing::assert_yields(&mut $generator, ::std::vec![$($value),*])
+    };
+}
+
+/// Resumes a coroutine with the given resume values and asserts that it completes with the given
+/// value on the last one.
+///
+/// ```
+/// use async_coroutine::{Coroutine, assert_completes};
+///
+/// let mut co = Coroutine::new(|handle, value| async move {
+///     value + handle.yield_(()).await
+/// });
+///
+/// assert_completes!(co, resumes = [1, 2], 3);
+/// ```
+#[macro_export]
+macro_rules! assert_completes {
+    ($coroutine:expr, resumes = [$($resume:expr),* $(,)?], $value:expr $(,)?) => {
+        $crate::testing::assert_completes(
+            &mut $coroutine,
+            ::std::vec![$($resume),*],
+            $value,
+        )
+    };
+}
+
+/// The function behind [`assert_yields!`].
+#[track_caller]
+pub fn assert_yields<Y, T>(generator: &mut Generator<'_, Y, T>, expected: Vec<Y>)
+where
+    Y: Debug + PartialEq,
+    T: Debug,
+{
+    let mut actual = Vec::with_capacity(expected.len());
+    while actual.len() < expected.len() {
+        match generator.resume() {
+            State::Yield(value) => actual.push(value),
+            State::Complete(value) => {
+                assert_eq!(actual, expected, "coroutine completed early with {value:?}");
+            }
+        }
+    }
+    assert_eq!(actual, expected, "coroutine yielded different values");
+}
+
+/// The function behind [`assert_completes!`].
+#[track_caller]
+pub fn assert_completes<Y, T, R>(
+    coroutine: &mut Coroutine<'_, Y, T, R>,
+    resumes: Vec<R>,
+    expected: T,
+) where
+    Y: Debug,
+    T: Debug + PartialEq,
+{
+    let count = resumes.len();
+    let mut yields = Vec::new();
+    for (index, resume) in resumes.into_iter().enumerate() {
+        match coroutine.resume_with(resume) {
+            State::Yield(value) => yields.push(value),
+            State::Complete(value) => {
+                assert!(
+                    index + 1 == count,
+                    "coroutine completed early with {value:?} after {} of {count} resume values, \
+                     it yielded {yields:?}",
+                    index + 1,
+                );
+                assert_eq!(
+                    value, expected,
+                    "coroutine completed with a different value, it yielded {yields:?}"
+                );
+                return;
+            }
+        }
+    }
+    panic!("coroutine did not complete after {count} resume values, it yielded {yields:?}");
+}
+
+/// Creates scripted coroutines that assert the resume values they receive and play back
+/// configured yields.
+///
+/// ```
+/// use async_coroutine::{State, testing::MockCoroutine};
+///
+/// let mut co = MockCoroutine::script()
+///     .expect_resume(1)
+///     .yield_(10)
+///     .expect_resume(2)
+///     .complete(42);
+///
+/// assert_eq!(co.resume_with(1), State::Yield(10));
+/// assert_eq!(co.resume_with(2), State::Complete(42));
+/// ```
+#[derive(Debug)]
+pub struct MockCoroutine;
+
+impl MockCoroutine {
+    /// Starts an empty [`Script`].
+    pub fn script<Y, R>() -> Script<Y, R> {
+        Script { steps: Vec::new() }
+    }
+}
+
+/// The script of a mock coroutine, created by [`MockCoroutine::script`].
+#[derive(Debug, Clone)]
+pub struct Script<Y, R> {
+    steps: Vec<Step<Y, R>>,
+}
+
+#[derive(Debug, Clone)]
+enum Step<Y, R> {
+    ExpectResume(R),
+    Yield(Y),
+}
+
+impl<Y, R> Script<Y, R>
+where
+    Y: 'static,
+    R: Debug + PartialEq + 'static,
+{
+    /// Expects the coroutine to be resumed with the given value next.
+    ///
+    /// The first expectation refers to the initial value, later ones to the value the preceding
+    /// yield is resumed with. Resume values without an expectation are ignored.
+    pub fn expect_resume(mut self, resume: R) -> Self {
+        self.steps.push(Step::ExpectResume(resume));
+        self
+    }
+
+    /// Yields the given value next.
+    pub fn yield_(mut self, value: Y) -> Self {
+        self.steps.push(Step::Yield(value));
+        self
+    }
+
+    /// Completes the script with the given value and creates the coroutine.
+    ///
+    /// The coroutine panics if it is resumed with a value that does not match the expectation.
+    #[track_caller]
+    pub fn complete<T>(self, value: T) -> Coroutine<'static, Y, T, R>
+    where
+        T: 'static,
+    {
+        Coroutine::builder()
+            .name("mock")
+            .build(move |handle, initial| async move {
+                play(&handle, initial, self.steps).await;
+                value
+            })
+    }
+}
+
+async fn play<Y, R>(handle: &YieldHandle<Y, R>, initial: R, steps: Vec<Step<Y, R>>)
+where
+    R: Debug + PartialEq,
+{
+    let mut resume = Some(initial);
+    for (index, step) in steps.into_iter().enumerate() {
+        match step {
+            Step::ExpectResume(expected) => {
+                let Some(resume) = resume.take() else {
+                    panic!("step {index}: expected resume {expected:?} after another expectation");
+                };
+                assert_eq!(resume, expected, "step {index}: unexpected resume value");
+            }
+            Step::Yield(value) => resume = Some(handle.yield_(value).await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockCoroutine;
+    use crate::{Coroutine, Generator, State};
+
+    #[test]
+    fn test_assert_yields() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            for value in 1..=3 {
+                handle.yield_(value).await;
+            }
+        });
+
+        assert_yields!(generator, [1, 2]);
+        assert_yields!(generator, [3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "coroutine completed early with ()")]
+    fn test_assert_yields_completed_early() {
+        let mut generator = Generator::new(|handle, ()| async move {
+            handle.yield_(1).await;
+        });
+
+        assert_yields!(generator, [1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "coroutine did not complete after 1 resume values")]
+    fn test_assert_completes_incomplete() {
+        let mut co = Coroutine::new(|handle, value| async move { handle.yield_(value).await });
+
+        assert_completes!(co, resumes = [1], 1);
+    }
+
+    #[test]
+    fn test_mock_coroutine() {
+        let mut co = MockCoroutine::script()
+            .yield_('a')
+            .expect_resume(2)
+            .yield_('b')
+            .yield_('c')
+            .complete("Bye");
+
+        assert_eq!(co.resume_with(1), State::Yield('a'));
+        assert_eq!(co.resume_with(2), State::Yield('b'));
+        assert_eq!(co.resume_with(3), State::Yield('c'));
+        assert_eq!(co.resume_with(4), State::Complete("Bye"));
+    }
+
+    #[test]
+    #[should_panic(expected = "step 2: unexpected resume value")]
+    fn test_mock_coroutine_unexpected_resume() {
+        let mut co = MockCoroutine::script()
+            .expect_resume(1)
+            .yield_(())
+            .expect_resume(2)
+            .complete(());
+
+        co.resume_with(1);
+        co.resume_with(3); // This panics
+    }
+}
@@ -0,0 +1,326 @@
+//! Recording the resumes and yields of coroutines to visualize how they interleave.
+//!
+//! A [`Trace`] is a sink that is shared by the coroutines it records. Every coroutine gets its own
+//! [`Trace::observer`], and the recorded [`Event`]s can be exported as a Mermaid or Graphviz
+//! sequence diagram, or as a Chrome trace-event JSON for `chrome://tracing` or Perfetto.
+//!
+//! ```
+//! use async_coroutine::{Generator, trace::Trace};
+//!
+//! let trace = Trace::new();
+//! let mut generator = Generator::builder()
+//!     .observer(trace.observer("numbers"))
+//!     .build(|handle, ()| async move {
+//!         handle.yield_(1).await;
+//!     });
+//! generator.resume();
+//! generator.resume();
+//!
+//! assert_eq!(
+//!     trace.to_mermaid(),
+//!     "sequenceDiagram\n\
+//!      \x20   participant driver\n\
+//!      \x20   participant numbers\n\
+//!      \x20   driver->>numbers: resume\n\
+//!      \x20   numbers-->>driver: yield\n\
+//!      \x20   driver->>numbers: resume\n\
+//!      \x20   numbers-->>driver: complete\n",
+//! );
+//! ```
+
+use crate::Observer;
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// What happened to a coroutine in an [`Event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// The coroutine was resumed.
+    Resume,
+    /// The coroutine yielded a value.
+    Yield,
+    /// The coroutine completed.
+    Complete,
+    /// The coroutine was dropped before it completed.
+    Drop,
+}
+
+impl EventKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Resume => "resume",
+            Self::Yield => "yield",
+            Self::Complete => "complete",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+/// An event recorded by a [`Trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Identifies the coroutine, in the order the observers were created.
+    pub coroutine: usize,
+    /// What happened.
+    pub kind: EventKind,
+    /// The time since the trace was created.
+    pub time: Duration,
+}
+
+/// A sink for the events of one or more coroutines.
+///
+/// Clones share the same recorded events.
+#[derive(Clone)]
+pub struct Trace {
+    inner: Rc<RefCell<Inner>>,
+}
+
+struct Inner {
+    start: Instant,
+    names: Vec<String>,
+    events: Vec<Event>,
+}
+
+impl Trace {
+    /// Creates an empty trace.
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(Inner {
+                start: Instant::now(),
+                names: Vec::new(),
+                events: Vec::new(),
+            })),
+        }
+    }
+
+    /// Creates an observer that records the events of a coroutine under the given name, to be
+    /// installed with [`CoroutineBuilder::observer`](crate::CoroutineBuilder::observer).
+    pub fn observer(&self, name: impl Into<String>) -> TraceObserver {
+        let mut inner = self.inner.borrow_mut();
+        inner.names.push(name.into());
+        TraceObserver {
+            coroutine: inner.names.len() - 1,
+            trace: self.clone(),
+        }
+    }
+
+    /// Returns the recorded events in order.
+    pub fn events(&self) -> Vec<Event> {
+        self.inner.borrow().events.clone()
+    }
+
+    /// Returns the name the coroutine with the given id was registered with.
+    pub fn name(&self, coroutine: usize) -> Option<String> {
+        self.inner.borrow().names.get(coroutine).cloned()
+    }
+
+    /// Removes all recorded events.
+    pub fn clear(&self) {
+        self.inner.borrow_mut().events.clear();
+    }
+
+    /// Exports the events as a Mermaid sequence diagram between the driver and the coroutines.
+    pub fn to_mermaid(&self) -> String {
+        let inner = self.inner.borrow();
+        let mut out = String::from("sequenceDiagram\n    participant driver\n");
+        for name in &inner.names {
+            writeln!(out, "    participant {name}").unwrap();
+        }
+        for event in &inner.events {
+            let name = &inner.names[event.coroutine];
+            let label = event.kind.label();
+            match event.kind {
+                EventKind::Resume => writeln!(out, "    driver->>{name}: {label}"),
+                EventKind::Yield | EventKind::Complete => {
+                    writeln!(out, "    {name}-->>driver: {label}")
+                }
+                EventKind::Drop => writeln!(out, "    Note over {name}: {label}"),
+            }
+            .unwrap();
+        }
+        out
+    }
+
+    /// Exports the events as a Graphviz digraph, which draws the events as a chain in the lane of
+    /// their coroutine.
+    pub fn to_graphviz(&self) -> String {
+        let inner = self.inner.borrow();
+        let mut out = String::from("digraph trace {\n    rankdir=TB;\n");
+        for (coroutine, name) in inner.names.iter().enumerate() {
+            writeln!(out, "    subgraph cluster_{coroutine} {{").unwrap();
+            writeln!(out, "        label={};", quote(name)).unwrap();
+            for (index, event) in inner.events.iter().enumerate() {
+                if event.coroutine == coroutine {
+                    writeln!(
+                        out,
+                        "        e{index} [label={}];",
+                        quote(&format!("{} @ {:?}", event.kind.label(), event.time)),
+                    )
+                    .unwrap();
+                }
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+        for index in 1..inner.events.len() {
+            writeln!(out, "    e{} -> e{index};", index - 1).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Exports the events in the Chrome trace-event JSON format, with a thread per coroutine and a
+    /// duration event from every resume to the following yield or completion.
+    pub fn to_chrome_json(&self) -> String {
+        let inner = self.inner.borrow();
+        let mut records = Vec::new();
+        for (coroutine, name) in inner.names.iter().enumerate() {
+            records.push(format!(
+                r#"{{"name":"thread_name","ph":"M","pid":0,"tid":{coroutine},"args":{{"name":{}}}}}"#,
+                quote(name),
+            ));
+        }
+        for event in &inner.events {
+            let phase = match event.kind {
+                EventKind::Resume => "B",
+                EventKind::Yield | EventKind::Complete => "E",
+                EventKind::Drop => "i",
+            };
+            records.push(format!(
+                r#"{{"name":"{}","ph":"{phase}","ts":{},"pid":0,"tid":{}}}"#,
+                event.kind.label(),
+                event.time.as_micros(),
+                event.coroutine,
+            ));
+        }
+        format!("[{}]", records.join(","))
+    }
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The observer that records the events of a coroutine in a [`Trace`], created by
+/// [`Trace::observer`].
+pub struct TraceObserver {
+    coroutine: usize,
+    trace: Trace,
+}
+
+impl TraceObserver {
+    fn record(&self, kind: EventKind) {
+        let mut inner = self.trace.inner.borrow_mut();
+        let time = inner.start.elapsed();
+        inner.events.push(Event {
+            coroutine: self.coroutine,
+            kind,
+            time,
+        });
+    }
+}
+
+impl<Y, T, R> Observer<Y, T, R> for TraceObserver {
+    fn on_resume(&mut self, _resume: &R) {
+        self.record(EventKind::Resume);
+    }
+
+    fn on_yield(&mut self, _value: &Y) {
+        self.record(EventKind::Yield);
+    }
+
+    fn on_complete(&mut self, _value: &T) {
+        self.record(EventKind::Complete);
+    }
+
+    fn on_drop_suspended(&mut self) {
+        self.record(EventKind::Drop);
+    }
+}
+
+// Quotes a string for Graphviz and JSON, which share the escapes needed here
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventKind, Trace};
+    use crate::{Coroutine, Generator};
+
+    #[test]
+    fn test_trace_interleaving() {
+        let trace = Trace::new();
+        let mut producer = Generator::builder()
+            .observer(trace.observer("producer"))
+            .build(|handle, ()| async move {
+                for value in 0..2 {
+                    handle.yield_(value).await;
+                }
+            });
+        let mut consumer = Coroutine::<(), (), i32>::builder()
+            .observer(trace.observer("consumer \"sink\""))
+            .build(|handle, mut value| async move {
+                while value >= 0 {
+                    value = handle.recv().await;
+                }
+            });
+
+        while let Some(value) = producer.resume().into_yield() {
+            consumer.resume_with(value);
+        }
+        drop(consumer);
+
+        let kinds = trace
+            .events()
+            .iter()
+            .map(|event| (event.coroutine, event.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (0, EventKind::Resume),
+                (0, EventKind::Yield),
+                (1, EventKind::Resume),
+                (1, EventKind::Yield),
+                (0, EventKind::Resume),
+                (0, EventKind::Yield),
+                (1, EventKind::Resume),
+                (1, EventKind::Yield),
+                (0, EventKind::Resume),
+                (0, EventKind::Complete),
+                (1, EventKind::Drop),
+            ]
+        );
+        assert_eq!(trace.name(1).as_deref(), Some("consumer \"sink\""));
+
+        let graphviz = trace.to_graphviz();
+        assert!(graphviz.starts_with("digraph trace {\n"));
+        assert!(graphviz.contains("label=\"consumer \\\"sink\\\"\";"));
+        assert!(graphviz.contains("    e9 -> e10;\n"));
+
+        let chrome = trace.to_chrome_json();
+        assert!(chrome.starts_with(r#"[{"name":"thread_name","ph":"M","pid":0,"tid":0,"#));
+        assert_eq!(chrome.matches(r#""ph":"B""#).count(), 5);
+        assert_eq!(chrome.matches(r#""ph":"E""#).count(), 5);
+        assert_eq!(chrome.matches(r#""ph":"i""#).count(), 1);
+
+        trace.clear();
+        assert!(trace.events().is_empty());
+    }
+}
@@ -0,0 +1,52 @@
+//! Exposing generators to JavaScript with `wasm-bindgen`.
+
+use crate::{Generator, State};
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+impl Generator<'static, JsValue, JsValue> {
+    /// Wraps the generator into a [`JsGenerator`] that can be handed to JavaScript.
+    pub fn into_js(self) -> JsGenerator {
+        JsGenerator {
+            generator: Some(self),
+        }
+    }
+}
+
+/// A generator that implements the JavaScript iterator protocol, created by
+/// [`Generator::into_js`].
+///
+/// Every call of `next()` resumes the generator and returns a `{ value, done }` result. The
+/// completion value is returned with `done: true`, afterwards `value` is `undefined`. To use the
+/// generator in a `for..of` loop, wrap it in an iterable:
+///
+/// ```js
+/// for (const value of { [Symbol.iterator]: () => generator }) {
+///     console.log(value);
+/// }
+/// ```
+#[wasm_bindgen]
+pub struct JsGenerator {
+    generator: Option<Generator<'static, JsValue, JsValue>>,
+}
+
+#[wasm_bindgen]
+impl JsGenerator {
+    /// Resumes the generator and returns the next iterator result, exposed as `next()`.
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_result(&mut self) -> Result<Object, JsValue> {
+        let (value, done) = match self.generator.as_mut().map(Generator::resume) {
+            Some(State::Yield(value)) => (value, false),
+            Some(State::Complete(value)) => {
+                self.generator = None;
+                (value, true)
+            }
+            None => (JsValue::UNDEFINED, true),
+        };
+
+        let result = Object::new();
+        Reflect::set(&result, &"value".into(), &value)?;
+        Reflect::set(&result, &"done".into(), &done.into())?;
+        Ok(result)
+    }
+}
@@ -13,9 +13,11 @@ struct YieldNow(bool);
 impl Future for YieldNow {
     type Output = ();
 
-    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         if !self.0 {
             self.0 = true;
+            // Drivers that await the resume are polled again right away
+            cx.waker().wake_by_ref();
             Poll::Pending
         } else {
             Poll::Ready(())